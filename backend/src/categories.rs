@@ -0,0 +1,174 @@
+// src/categories.rs
+
+use std::path::Path;
+
+use ausgabenzettel_core::{
+    Kind,
+    category::{self, Category, ancestors, tree},
+};
+use quick_xml::escape::escape;
+
+/// The categories file shipped with the backend, used when none is configured.
+pub const DEFAULT: &str = include_str!("../initial/categories.json");
+
+/// Marker within the style of the head template that is replaced by the
+/// category colours.
+const STYLE_MARKER: &str = "/* categories */";
+/// Marker within `#daily_label_select` that is replaced by the category options.
+const OPTIONS_MARKER: &str = "<!-- categories -->";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Invalid(#[from] category::Error),
+}
+
+/// Colour used when a category does not define one.
+const FALLBACK_COLOR: &str = "#cccccc";
+
+pub fn parse(content: &str) -> Result<Vec<Category>, Error> {
+    Ok(category::parse(content)?)
+}
+
+pub async fn load(path: &Path) -> Result<Vec<Category>, Error> {
+    let content = fs_err::tokio::read_to_string(path).await?;
+    parse(&content)
+}
+
+/// Renders the colour variable and class of each category.
+///
+/// Subcategories without a colour use the one of their closest parent.
+pub fn render_style(categories: &[Category]) -> String {
+    let mut variables = String::from(":root {\n");
+    let mut classes = String::new();
+    for category in categories {
+        let class = category.class();
+        let color = ancestors(categories, category)
+            .iter()
+            .find_map(|x| x.color.as_deref())
+            .unwrap_or(FALLBACK_COLOR);
+        variables.push_str(&format!("    --{class}: {color};\n"));
+        classes.push_str(&format!(
+            ".{class},\n.{class} * {{\n    background-color: var(--{class});\n}}\n\n"
+        ));
+    }
+    variables.push_str("}\n\n");
+    variables.push_str(&classes);
+    variables
+}
+
+/// Renders the options of the label drop down, subcategories following their parent.
+///
+/// Income categories are marked with `data-kind="income"`.
+pub fn render_options(categories: &[Category]) -> String {
+    tree(categories)
+        .into_iter()
+        .map(|(_, x)| {
+            let parent = x
+                .parent
+                .as_ref()
+                .map(|p| format!(" data-parent=\"{}\"", escape(p)))
+                .unwrap_or_default();
+            let kind = match x.kind {
+                Kind::Income => " data-kind=\"income\"",
+                _ => "",
+            };
+            format!(
+                "<option value=\"{}\" title=\"{}\"{parent}{kind}>{}</option>",
+                escape(&x.id),
+                escape(&x.description),
+                escape(&x.title)
+            )
+        })
+        .collect()
+}
+
+/// Replaces the category markers within the given head template.
+pub fn render_head(template: &str, categories: &[Category]) -> String {
+    template
+        .replacen(STYLE_MARKER, &render_style(categories), 1)
+        .replacen(OPTIONS_MARKER, &render_options(categories), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: &str, title: &str, color: Option<&str>) -> Category {
+        Category {
+            description: format!("{title} description"),
+            color: color.map(|x| x.to_string()),
+            ..Category::new(id, title)
+        }
+    }
+
+    #[test]
+    fn test_default_parses() {
+        let categories = parse(DEFAULT).unwrap();
+        assert!(!categories.is_empty());
+        assert!(categories.iter().all(|x| x.color.is_some()));
+    }
+
+    #[test]
+    fn test_render_style() {
+        let style = render_style(&[category("alltag", "Alltag", Some("#f7910c"))]);
+        assert!(style.contains("--cat-alltag: #f7910c;"));
+        assert!(style.contains(".cat-alltag * {\n    background-color: var(--cat-alltag);"));
+    }
+
+    #[test]
+    fn test_render_style_fallback_color() {
+        let style = render_style(&[category("reisen", "Reisen", None)]);
+        assert!(style.contains("--cat-reisen: #cccccc;"));
+    }
+
+    #[test]
+    fn test_render_options_escapes() {
+        let options = render_options(&[category("beauty-wellness", "Beauty & Wellness", None)]);
+        assert_eq!(
+            options,
+            "<option value=\"beauty-wellness\" title=\"Beauty &amp; Wellness description\">Beauty &amp; Wellness</option>"
+        );
+    }
+
+    #[test]
+    fn test_render_head() {
+        let template = "<style>/* categories */</style><select><!-- categories --></select>";
+        let head = render_head(template, &[category("alltag", "Alltag", None)]);
+        assert!(!head.contains(STYLE_MARKER));
+        assert!(!head.contains(OPTIONS_MARKER));
+        assert!(head.contains("<option value=\"alltag\""));
+    }
+
+    #[test]
+    fn test_subcategories() {
+        let mut drogerie = category("drogerie", "Drogerie", None);
+        drogerie.parent = Some("alltag".to_string());
+        let categories = [
+            drogerie,
+            category("ausgehen", "Ausgehen", None),
+            category("alltag", "Alltag", Some("#f7910c")),
+        ];
+        let style = render_style(&categories);
+        assert!(style.contains("--cat-drogerie: #f7910c;"));
+        let options = render_options(&categories);
+        let alltag = options.find("value=\"alltag\"").unwrap();
+        let drogerie = options.find("value=\"drogerie\"").unwrap();
+        let ausgehen = options.find("value=\"ausgehen\"").unwrap();
+        assert!(ausgehen < alltag && alltag < drogerie);
+        assert!(options.contains("data-parent=\"alltag\""));
+    }
+
+    #[test]
+    fn test_render_options_income() {
+        let mut gehalt = category("gehalt", "Gehalt", None);
+        gehalt.kind = Kind::Income;
+        let options = render_options(&[gehalt, category("alltag", "Alltag", None)]);
+        assert!(options.starts_with(
+            "<option value=\"gehalt\" title=\"Gehalt description\" data-kind=\"income\">"
+        ));
+        assert_eq!(options.matches("data-kind").count(), 1);
+    }
+}
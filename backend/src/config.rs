@@ -6,7 +6,11 @@ use std::{
 
 use axum_server::tls_rustls::RustlsConfig;
 
+use ausgabenzettel_core::Category;
+
+use crate::categories;
 use crate::certs;
+use crate::rates::{self, ExchangeRates};
 
 pub struct Certificates {
     server_cert: PathBuf,
@@ -53,9 +57,31 @@ pub enum Error {
     UnableToCreateDataDir(#[from] std::io::Error),
     #[error(transparent)]
     InvalidAddres(#[from] AddrParseError),
+    #[error("{0} invalid categories: {1}")]
+    InvalidCategories(PathBuf, categories::Error),
+    #[error("{0} invalid exchange rates: {1}")]
+    InvalidRates(PathBuf, rates::Error),
 }
 
 const APPLICATION_NAME: &str = "ausgabenzettel";
+const CATEGORIES_FILE: &str = "categories.json";
+const RATES_FILE: &str = "rates.csv";
+
+/// Returns the user and the system configuration directory.
+fn config_dirs() -> (PathBuf, PathBuf) {
+    let user = env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| {
+            env::var("HOME")
+                .map(PathBuf::from)
+                .map(|x| x.join(".config"))
+        })
+        .unwrap_or_else(|_| PathBuf::from("~/.config"))
+        .join(APPLICATION_NAME);
+
+    let system = PathBuf::from("/etc").join(APPLICATION_NAME);
+    (user, system)
+}
 
 impl Certificates {
     fn check_for(user: &Path, system: &Path, kind: CertificateKind) -> Result<PathBuf, Error> {
@@ -73,21 +99,10 @@ impl Certificates {
         Err(Error::NotFound(Kind::Certificate(kind)))
     }
 
-    pub fn init() -> Result<Certificates, Error> {
-        let user = env::var("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .or_else(|_| {
-                env::var("HOME")
-                    .map(PathBuf::from)
-                    .map(|x| x.join(".config"))
-            })
-            .unwrap_or_else(|_| PathBuf::from("~/.config"))
-            .join(APPLICATION_NAME);
-
-        let system = PathBuf::from("/etc").join(APPLICATION_NAME);
-        let server_cert = Self::check_for(&user, &system, CertificateKind::ServerCert)?;
-        let server_key = Self::check_for(&user, &system, CertificateKind::ServerKey)?;
-        let client_ca = Self::check_for(&user, &system, CertificateKind::ClientCa)?;
+    pub fn init(user: &Path, system: &Path) -> Result<Certificates, Error> {
+        let server_cert = Self::check_for(user, system, CertificateKind::ServerCert)?;
+        let server_key = Self::check_for(user, system, CertificateKind::ServerKey)?;
+        let client_ca = Self::check_for(user, system, CertificateKind::ClientCa)?;
         Ok(Certificates {
             server_cert,
             server_key,
@@ -104,12 +119,29 @@ impl Certificates {
 
 pub struct BasePaths {
     certificates: Certificates,
+    categories: Option<PathBuf>,
+    rates: Option<PathBuf>,
     client_data: PathBuf,
 }
 
 impl BasePaths {
+    fn find(user: &Path, system: &Path, file: &str) -> Option<PathBuf> {
+        let found = [user, system]
+            .into_iter()
+            .map(|x| x.join(file))
+            .find(|x| x.is_file());
+        match found.as_ref() {
+            Some(path) => tracing::debug!(?path, file),
+            None => tracing::info!(?user, ?system, file, "not found, using defaults"),
+        }
+        found
+    }
+
     pub fn init() -> Result<Self, Error> {
-        let certificates = Certificates::init()?;
+        let (user, system) = config_dirs();
+        let certificates = Certificates::init(&user, &system)?;
+        let categories = Self::find(&user, &system, CATEGORIES_FILE);
+        let rates = Self::find(&user, &system, RATES_FILE);
         let user_data_path = env::var("XDG_RUNTIME_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("/var/lib"))
@@ -125,6 +157,8 @@ impl BasePaths {
 
         Ok(BasePaths {
             certificates,
+            categories,
+            rates,
             client_data: user_data_path,
         })
     }
@@ -134,6 +168,8 @@ pub struct Config {
     pub tls: RustlsConfig,
     pub listening: SocketAddr,
     pub upload_dir: PathBuf,
+    pub categories: Vec<Category>,
+    pub rates: ExchangeRates,
 }
 
 impl Config {
@@ -144,11 +180,30 @@ impl Config {
             .parse()?;
         let tls = paths.certificates.into_rustls_config().await?;
         let upload_dir = paths.client_data;
+        let categories = match paths.categories {
+            Some(path) => categories::load(&path)
+                .await
+                .map_err(|e| Error::InvalidCategories(path, e))?,
+            None => {
+                categories::parse(categories::DEFAULT).expect("bundled categories must be valid")
+            }
+        };
+
+        let base_currency =
+            env::var("AUSGABENZETTEL_BASE_CURRENCY").unwrap_or_else(|_| "EUR".into());
+        let rates = match paths.rates {
+            Some(path) => ExchangeRates::load(&base_currency, &path)
+                .await
+                .map_err(|e| Error::InvalidRates(path, e))?,
+            None => ExchangeRates::new(&base_currency),
+        };
 
         Ok(Self {
             listening,
             tls,
             upload_dir,
+            categories,
+            rates,
         })
     }
 }
@@ -1,10 +1,13 @@
+mod categories;
 mod certs;
 mod config;
 mod git;
-//mod parser;
+mod rates;
+mod summary;
 
+use ausgabenzettel_core::{Category, document};
 use axum::{
-    BoxError, Router,
+    BoxError, Json, Router,
     body::{Body, Bytes},
     extract::Path as APath,
     extract::{Request, State},
@@ -17,7 +20,7 @@ use futures_util::TryStreamExt;
 use futures_util::{Stream, StreamExt};
 use ring::digest::{Context, SHA256};
 use std::{
-    io::{self},
+    io::{self, Cursor},
     path::{Path, PathBuf},
     pin::pin,
     sync::{
@@ -37,20 +40,30 @@ enum ReturnType {
 #[derive(Clone)]
 struct PageStreamer {
     upload: PathBuf,
+    /// head.template with the categories rendered into it
+    header: Bytes,
+    categories: Arc<Vec<Category>>,
+    rates: Arc<rates::ExchangeRates>,
     empty_content_etag: String,
     write_process: Arc<AtomicBool>,
 }
 
 impl PageStreamer {
-    const HEADER: &'static [u8] = include_bytes!("../initial/head.template");
+    const HEADER: &'static str = include_str!("../initial/head.template");
     const EMPTY_CONTENT: &'static [u8] = include_bytes!("../initial/content.template");
     const TAIL: &'static [u8] = include_bytes!("../initial/tail.template");
-    fn path(&self, file: &str) -> Option<PathBuf> {
+    /// Returns the stored file of the name, None if there is none yet. Names
+    /// that could point outside of the upload directory are a bad request.
+    fn path(&self, file: &str) -> Result<Option<PathBuf>, StatusCode> {
+        if !path_is_valid(file) {
+            tracing::info!(file, "invalid");
+            return Err(StatusCode::BAD_REQUEST);
+        }
         let current = self.upload.join(file);
         if current.exists() && current.is_file() {
-            Some(current)
+            Ok(Some(current))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -67,8 +80,8 @@ impl PageStreamer {
         rt: ReturnType,
         status: StatusCode,
         content_path: String,
-    ) -> impl IntoResponse {
-        let path = self.path(&content_path);
+    ) -> Result<impl IntoResponse, StatusCode> {
+        let path = self.path(&content_path)?;
         let header = [
             (header::CONTENT_TYPE, "text/html".to_string()),
             (header::ETAG, self.etag(path.as_ref()).await),
@@ -76,27 +89,27 @@ impl PageStreamer {
         match rt {
             ReturnType::Full => {
                 // head.template, content, tail.template
-                let head = ReaderStream::new(Self::HEADER);
+                let head = ReaderStream::new(Cursor::new(self.header.clone()));
                 let tail = ReaderStream::new(Self::TAIL);
                 if let Some(path) = path.as_ref() {
                     let file = File::open(path).await.unwrap();
                     let content = ReaderStream::new(file);
                     let stream = head.chain(content).chain(tail);
-                    (status, header, Body::from_stream(stream))
+                    Ok((status, header, Body::from_stream(stream)))
                 } else {
                     let content = ReaderStream::new(Self::EMPTY_CONTENT);
                     let stream = head.chain(content).chain(tail);
-                    (status, header, Body::from_stream(stream))
+                    Ok((status, header, Body::from_stream(stream)))
                 }
             }
             ReturnType::Content => {
                 if let Some(path) = path.as_ref() {
                     let file = File::open(path).await.unwrap();
                     let content = ReaderStream::new(file);
-                    (status, header, Body::from_stream(content))
+                    Ok((status, header, Body::from_stream(content)))
                 } else {
                     let content = ReaderStream::new(Self::EMPTY_CONTENT);
-                    (status, header, Body::from_stream(content))
+                    Ok((status, header, Body::from_stream(content)))
                 }
             }
         }
@@ -113,15 +126,36 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // Other crates of the workspace may enable further providers, which rustls
+    // refuses to choose from.
+    if rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .is_err()
+    {
+        tracing::debug!("crypto provider already installed");
+    }
+
     let config = config::Config::init().await?;
     let ps = PageStreamer {
         upload: config.upload_dir,
+        header: config
+            .rates
+            .render_head(&categories::render_head(
+                PageStreamer::HEADER,
+                &config.categories,
+            ))
+            .into(),
+        categories: Arc::new(config.categories),
+        rates: Arc::new(config.rates),
         empty_content_etag: "INITIAL".into(),
         write_process: Arc::new(AtomicBool::default()),
     };
 
     let app = Router::new()
         .route("/", get(redirect_to_year))
+        .route("/_categories.json", get(get_categories))
+        .route("/_summary/{name}", get(get_summary))
+        .route("/_content/{name}", get(get_content))
         .route("/{name}", put(save))
         .route("/{name}", get(get_html))
         .route("/{name}", head(header))
@@ -140,11 +174,40 @@ async fn redirect_to_year() -> Redirect {
     Redirect::temporary(&format!("/{}", current_year))
 }
 
-async fn header(State(ps): State<PageStreamer>, APath(name): APath<String>) -> impl IntoResponse {
-    let path = ps.path(&name);
+async fn get_categories(State(ps): State<PageStreamer>) -> impl IntoResponse {
+    Json(ps.categories.as_ref().clone())
+}
+
+async fn get_summary(
+    State(ps): State<PageStreamer>,
+    APath(name): APath<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let content = match ps.path(&name)? {
+        Some(path) => tokio::fs::read_to_string(&path).await.map_err(|error| {
+            tracing::error!(name, %error, "Unable to read content");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+        None => String::from_utf8_lossy(PageStreamer::EMPTY_CONTENT).to_string(),
+    };
+    let parsed = document::parse(&content);
+    for warning in &parsed.warnings {
+        tracing::warn!(name, %warning, "Invalid content");
+    }
+    Ok(Json(summary::summarize(
+        &parsed.expenses,
+        &ps.categories,
+        &ps.rates,
+    )))
+}
+
+async fn header(
+    State(ps): State<PageStreamer>,
+    APath(name): APath<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let path = ps.path(&name)?;
     let etag = ps.etag(path.as_ref()).await;
     let header = [(header::ETAG, etag)];
-    (header, StatusCode::OK)
+    Ok((header, StatusCode::OK))
 }
 
 struct WriteGuard<'a>(&'a AtomicBool);
@@ -160,6 +223,7 @@ async fn save(
     APath(name): APath<String>,
     request: Request,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let path = ps.path(&name)?;
     let sc = match request.headers().get(header::IF_MATCH) {
         None => {
             tracing::warn!("if match header missing");
@@ -167,7 +231,7 @@ async fn save(
         }
         Some(etag) => {
             let etag = etag.to_str().unwrap();
-            let current_etag = ps.etag(ps.path(&name).as_ref()).await;
+            let current_etag = ps.etag(path.as_ref()).await;
             if etag != current_etag {
                 StatusCode::CONFLICT
             // FIXME: this blocks write access although they could be potentially on
@@ -186,7 +250,7 @@ async fn save(
         }
     };
 
-    Ok(ps.stream_file(ReturnType::Content, sc, name).await)
+    ps.stream_file(ReturnType::Content, sc, name).await
 }
 
 fn path_is_valid(s: &str) -> bool {
@@ -280,6 +344,18 @@ where
     }
 }
 
-async fn get_html(State(ps): State<PageStreamer>, APath(name): APath<String>) -> impl IntoResponse {
+/// Returns the stored `#details` content without head and tail.
+async fn get_content(
+    State(ps): State<PageStreamer>,
+    APath(name): APath<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    ps.stream_file(ReturnType::Content, StatusCode::OK, name)
+        .await
+}
+
+async fn get_html(
+    State(ps): State<PageStreamer>,
+    APath(name): APath<String>,
+) -> Result<impl IntoResponse, StatusCode> {
     ps.stream_file(ReturnType::Full, StatusCode::OK, name).await
 }
@@ -0,0 +1,214 @@
+// src/rates.rs
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use ausgabenzettel_core::{
+    Money,
+    money::{currency_code as code, currency_symbol as symbol},
+};
+use chrono::NaiveDate;
+use quick_xml::escape::escape;
+
+/// Marker within `#daily_currency` that is replaced by the currency options.
+const OPTIONS_MARKER: &str = "<!-- currencies -->";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("line {0}: expected date,currency,rate")]
+    InvalidLine(usize),
+    #[error("line {0}: invalid date '{1}'")]
+    InvalidDate(usize, String),
+    #[error("line {0}: invalid rate '{1}'")]
+    InvalidRate(usize, String),
+}
+
+/// Exchange rates to convert into a base currency.
+///
+/// Each rate is the amount of the currency that one unit of the base currency
+/// buys on the given date, e.g. `2026-01-30,USD,1.04`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRates {
+    base: String,
+    rates: HashMap<String, BTreeMap<NaiveDate, f64>>,
+}
+
+impl ExchangeRates {
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: code(base),
+            rates: HashMap::new(),
+        }
+    }
+
+    /// Parses lines of `date,currency,rate`.
+    ///
+    /// Empty lines, lines starting with `#` and a `date,currency,rate` header
+    /// are ignored.
+    pub fn parse(base: &str, content: &str) -> Result<Self, Error> {
+        let mut result = Self::new(base);
+        for (i, line) in content.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "date,currency,rate" {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(|x| x.trim()).collect();
+            let [date, currency, rate] = fields[..] else {
+                return Err(Error::InvalidLine(line_number));
+            };
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| Error::InvalidDate(line_number, date.to_string()))?;
+            let rate = rate
+                .parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite() && *x > 0.0)
+                .ok_or_else(|| Error::InvalidRate(line_number, rate.to_string()))?;
+            result
+                .rates
+                .entry(code(currency))
+                .or_default()
+                .insert(date, rate);
+        }
+        Ok(result)
+    }
+
+    pub async fn load(base: &str, path: &Path) -> Result<Self, Error> {
+        let content = fs_err::tokio::read_to_string(path).await?;
+        Self::parse(base, &content)
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Returns the base currency followed by all currencies with a rate.
+    pub fn currencies(&self) -> Vec<&str> {
+        let mut others: Vec<&str> = self
+            .rates
+            .keys()
+            .map(|x| x as &str)
+            .filter(|x| *x != self.base)
+            .collect();
+        others.sort();
+        let mut result = vec![&self.base as &str];
+        result.extend(others);
+        result
+    }
+
+    /// Returns the latest rate of the currency on or before the date.
+    ///
+    /// Without a date the most recent rate is used.
+    pub fn rate(&self, currency: &str, date: Option<NaiveDate>) -> Option<f64> {
+        let currency = code(currency);
+        if currency == self.base {
+            return Some(1.0);
+        }
+        let rates = self.rates.get(&currency)?;
+        match date {
+            Some(date) => rates.range(..=date).next_back().map(|(_, x)| *x),
+            None => rates.values().next_back().copied(),
+        }
+    }
+
+    /// Converts the amount into the base currency.
+    pub fn convert(&self, amount: &Money, date: Option<NaiveDate>) -> Option<Money> {
+        self.rate(&amount.currency, date)
+            .map(|rate| amount.convert(rate, &self.base))
+    }
+
+    /// Renders the options of the currency drop down, the base currency first.
+    pub fn render_options(&self) -> String {
+        self.currencies()
+            .into_iter()
+            .map(|x| {
+                format!(
+                    "<option value=\"{}\" data-rate=\"{}\">{}</option>",
+                    escape(symbol(x)),
+                    self.rate(x, None).unwrap_or(1.0),
+                    escape(x)
+                )
+            })
+            .collect()
+    }
+
+    /// Replaces the currency marker within the given head template.
+    pub fn render_head(&self, template: &str) -> String {
+        template.replacen(OPTIONS_MARKER, &self.render_options(), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATES: &str = "date,currency,rate
+# ECB reference rates
+2026-01-02,USD,1.10
+2026-01-30,USD,1.04
+2026-01-30,GBP,0.85
+";
+
+    fn date(s: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+    }
+
+    #[test]
+    fn test_rate_uses_latest_before_date() {
+        let rates = ExchangeRates::parse("EUR", RATES).unwrap();
+        assert_eq!(rates.rate("USD", date("2026-01-15")), Some(1.10));
+        assert_eq!(rates.rate("USD", date("2026-02-01")), Some(1.04));
+        assert_eq!(rates.rate("USD", date("2025-12-31")), None);
+        assert_eq!(rates.rate("USD", None), Some(1.04));
+        assert_eq!(rates.rate("€", date("2025-12-31")), Some(1.0));
+        assert_eq!(rates.rate("CHF", None), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        let rates = ExchangeRates::parse("EUR", RATES).unwrap();
+        assert_eq!(
+            rates.convert(&Money::new(1100, "USD"), date("2026-01-02")),
+            Some(Money::new(1000, "EUR"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            ExchangeRates::parse("EUR", "2026-01-02,USD"),
+            Err(Error::InvalidLine(1))
+        ));
+        assert!(matches!(
+            ExchangeRates::parse("EUR", "02.01.2026,USD,1.1"),
+            Err(Error::InvalidDate(1, _))
+        ));
+        assert!(matches!(
+            ExchangeRates::parse("EUR", "\n2026-01-02,USD,0"),
+            Err(Error::InvalidRate(2, _))
+        ));
+        assert!(matches!(
+            ExchangeRates::parse("EUR", "2026-01-02,USD,inf"),
+            Err(Error::InvalidRate(1, _))
+        ));
+        assert!(matches!(
+            ExchangeRates::parse("EUR", "2026-01-02,USD,NaN"),
+            Err(Error::InvalidRate(1, _))
+        ));
+    }
+
+    #[test]
+    fn test_render_options() {
+        let rates = ExchangeRates::parse("EUR", RATES).unwrap();
+        assert_eq!(
+            rates.render_options(),
+            "<option value=\"€\" data-rate=\"1\">EUR</option>\
+             <option value=\"GBP\" data-rate=\"0.85\">GBP</option>\
+             <option value=\"USD\" data-rate=\"1.04\">USD</option>"
+        );
+    }
+}
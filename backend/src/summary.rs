@@ -0,0 +1,145 @@
+// src/summary.rs
+
+use std::collections::BTreeMap;
+
+use ausgabenzettel_core::{Category, Expense, Kind, Money, category::ancestors};
+use serde::Serialize;
+
+use crate::rates::ExchangeRates;
+
+/// Key used for expenses without a known category.
+const UNKNOWN: &str = "unknown";
+/// Key used for income without a known category.
+const UNKNOWN_INCOME: &str = "unknown-income";
+
+/// Totals of a document converted into the base currency.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    /// Sum of all expenses, reduced by their refunds.
+    pub total: Money,
+    /// Sum of all income.
+    pub income: Money,
+    /// Net cash flow, income minus expenses.
+    pub net: Money,
+    /// Totals per category id, subcategories are included in their parents.
+    pub categories: BTreeMap<String, Money>,
+    /// Ids of expenses that could not be converted due to a missing rate.
+    pub unconverted: Vec<String>,
+}
+
+pub fn summarize(expenses: &[Expense], categories: &[Category], rates: &ExchangeRates) -> Summary {
+    let zero = Money::zero(rates.base());
+    let mut summary = Summary {
+        total: zero.clone(),
+        income: zero.clone(),
+        net: zero.clone(),
+        categories: BTreeMap::new(),
+        unconverted: Vec::new(),
+    };
+
+    for expense in expenses {
+        // transfers only move money between own accounts
+        if expense.kind == Kind::Transfer {
+            continue;
+        }
+        let Some(amount) = rates.convert(&expense.amount, expense.date()) else {
+            summary.unconverted.push(expense.id.clone());
+            continue;
+        };
+        let (amount, unknown) = match expense.kind {
+            Kind::Income if expense.is_refund() => {
+                summary.total += -amount.clone();
+                summary.net += amount.clone();
+                (-amount, UNKNOWN)
+            }
+            Kind::Expense => {
+                summary.total += amount.clone();
+                summary.net += -amount.clone();
+                (amount, UNKNOWN)
+            }
+            Kind::Income => {
+                summary.income += amount.clone();
+                summary.net += amount.clone();
+                (amount, UNKNOWN_INCOME)
+            }
+            Kind::Transfer => unreachable!("transfers are skipped"),
+        };
+
+        let category = expense
+            .category
+            .as_ref()
+            .and_then(|id| categories.iter().find(|x| &x.id == id));
+        match category {
+            Some(category) => {
+                for x in ancestors(categories, category) {
+                    *summary
+                        .categories
+                        .entry(x.id.clone())
+                        .or_insert_with(|| zero.clone()) += amount.clone();
+                }
+            }
+            None => {
+                *summary
+                    .categories
+                    .entry(unknown.to_string())
+                    .or_insert_with(|| zero.clone()) += amount
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ausgabenzettel_core::document;
+
+    const RATES: &str = "2026-01-01,USD,1.25";
+
+    fn category(id: &str, parent: Option<&str>) -> Category {
+        Category {
+            parent: parent.map(|x| x.to_string()),
+            ..Category::new(id, id)
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        // 2026-01-02 and 2026-01-03
+        let html = r#"<div id="details">
+<details class="cat-alltag" id="1767355200000"><summary><span>REWE</span><span>10.00€</span></summary></details>
+<details class="cat-drogerie" id="1767441600000"><summary><span>DM</span><span>5.00USD</span></summary></details>
+<details class="cat-unknown" id="1767441600001"><summary><span>Kiosk</span><span>1.00€</span></summary></details>
+<details class="cat-ausgehen" id="1767441600002"><summary><span>Pub</span><span>3.00CHF</span></summary></details>
+<details class="cat-gehalt" id="1767441600003" data-kind="income"><summary><span>ACME</span><span>100.00€</span></summary></details>
+<details class="cat-unknown" id="1767441600004" data-kind="income"><summary><span>Oma</span><span>25.00USD</span></summary></details>
+<details class="cat-drogerie" id="1767441600005" data-kind="income" data-refund-of="1767441600000"><summary><span>DM</span><span>1.25USD</span></summary></details>
+<details class="cat-unknown" id="1767441600006" data-kind="transfer"><summary><span>DKB</span><span>-500.00€</span></summary></details>
+</div>"#;
+        let categories = [
+            category("alltag", None),
+            category("drogerie", Some("alltag")),
+            category("ausgehen", None),
+            Category {
+                kind: Kind::Income,
+                ..category("gehalt", None)
+            },
+        ];
+        let rates = ExchangeRates::parse("EUR", RATES).unwrap();
+
+        let summary = summarize(&document::parse(html).expenses, &categories, &rates);
+
+        let eur = |minor| Money::new(minor, "EUR");
+        assert_eq!(summary.total, eur(1400));
+        assert_eq!(summary.income, eur(12000));
+        assert_eq!(summary.net, eur(10600));
+        assert_eq!(summary.categories.get("gehalt"), Some(&eur(10000)));
+        assert_eq!(summary.categories.get("unknown-income"), Some(&eur(2000)));
+        assert_eq!(summary.categories.get("alltag"), Some(&eur(1300)));
+        assert_eq!(summary.categories.get("drogerie"), Some(&eur(300)));
+        assert_eq!(summary.categories.get("unknown"), Some(&eur(100)));
+        assert_eq!(summary.categories.get("ausgehen"), None);
+        assert_eq!(summary.unconverted, vec!["1767441600002".to_string()]);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::rules::{CategoryLookupEntry, CsvRecord, matching_rules};
+use ausgabenzettel_core::{Category, Kind};
+use std::collections::BTreeSet;
+
+/// A transaction matched by rules of different categories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Index of the transaction within the records.
+    pub record: usize,
+    /// Indices of the matching rules, the deciding one first.
+    pub rules: Vec<usize>,
+}
+
+/// Findings of [`check`] for a lookup against the records.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub conflicts: Vec<Conflict>,
+    /// Rules matching transactions that are all decided by others, with the
+    /// indices of the deciding rules.
+    pub shadowed: Vec<(usize, Vec<usize>)>,
+    /// Rules matching none of the transactions.
+    pub unmatched: Vec<usize>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty() && self.shadowed.is_empty() && self.unmatched.is_empty()
+    }
+}
+
+/// Checks which rules decide the category of the records, transfers are left
+/// out like they are when categorizing.
+pub fn check(
+    records: &[CsvRecord],
+    lookup: &[CategoryLookupEntry],
+    categories: &[Category],
+) -> Report {
+    let mut report = Report::default();
+    let mut matched = vec![false; lookup.len()];
+    let mut deciding: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); lookup.len()];
+
+    for (index, record) in records.iter().enumerate() {
+        if record.kind() == Kind::Transfer {
+            continue;
+        }
+        let rules = matching_rules(record, lookup, categories);
+        let Some(&(first, category)) = rules.first() else {
+            continue;
+        };
+        for (rule, _) in &rules {
+            matched[*rule] = true;
+            deciding[*rule].insert(first);
+        }
+        if rules.iter().any(|(_, x)| x.id != category.id) {
+            report.conflicts.push(Conflict {
+                record: index,
+                rules: rules.iter().map(|(x, _)| *x).collect(),
+            });
+        }
+    }
+
+    for (rule, deciding) in deciding.into_iter().enumerate() {
+        if !matched[rule] {
+            report.unmatched.push(rule);
+        } else if !deciding.contains(&rule) {
+            report.shadowed.push((rule, deciding.into_iter().collect()));
+        }
+    }
+    report
+}
+
+/// Describes a rule like `rule 3 (alltag)`, indices start at 0 like in
+/// the errors of the lookup.
+fn describe(lookup: &[CategoryLookupEntry], rule: usize) -> String {
+    let entry = &lookup[rule];
+    if entry.priority == 0 {
+        format!("rule {} ({})", rule, entry.category)
+    } else {
+        format!(
+            "rule {} ({}, priority {})",
+            rule, entry.category, entry.priority
+        )
+    }
+}
+
+/// The lines printed by `rules check`.
+pub fn format(
+    report: &Report,
+    records: &[CsvRecord],
+    lookup: &[CategoryLookupEntry],
+    categories: &[Category],
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !report.conflicts.is_empty() {
+        lines.push("# conflicts".to_string());
+        for conflict in &report.conflicts {
+            let record = &records[conflict.record];
+            let others: Vec<String> = conflict.rules[1..]
+                .iter()
+                .map(|x| describe(lookup, *x))
+                .collect();
+            lines.push(format!(
+                "{} {} {}: {} wins over {}",
+                record.booking_date,
+                record.amount,
+                record.get_title(),
+                describe(lookup, conflict.rules[0]),
+                others.join(", ")
+            ));
+        }
+    }
+    if !report.shadowed.is_empty() {
+        lines.push("# shadowed rules".to_string());
+        for (rule, deciding) in &report.shadowed {
+            let deciding: Vec<String> = deciding.iter().map(|x| describe(lookup, *x)).collect();
+            lines.push(format!(
+                "{}: always decided by {}",
+                describe(lookup, *rule),
+                deciding.join(", ")
+            ));
+        }
+    }
+    if !report.unmatched.is_empty() {
+        lines.push("# rules without match".to_string());
+        for rule in &report.unmatched {
+            let category = &lookup[*rule].category;
+            if categories.iter().any(|x| &x.id == category) {
+                lines.push(describe(lookup, *rule));
+            } else {
+                lines.push(format!("{}: unknown category", describe(lookup, *rule)));
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::MatchType;
+    use chrono::NaiveDate;
+
+    fn record(name: &str, reference: &str) -> CsvRecord {
+        CsvRecord {
+            booking_date: NaiveDate::from_ymd_opt(2026, 1, 2).unwrap(),
+            reference: reference.to_string(),
+            ..CsvRecord::test(name, "-12,50")
+        }
+    }
+
+    fn category(id: &str) -> Category {
+        Category::new(id, id)
+    }
+
+    #[test]
+    fn test_check() {
+        let categories = vec![category("alltag"), category("ausgehen")];
+        let lookup = vec![
+            CategoryLookupEntry::new("NAME", "REWE", "alltag", MatchType::Contains),
+            CategoryLookupEntry::new("REFERENCE", "Bar", "ausgehen", MatchType::Contains),
+            CategoryLookupEntry::new("NAME", "REWE Markt", "alltag", MatchType::Exact),
+            CategoryLookupEntry::new("NAME", "Kino", "ausgehen", MatchType::Exact),
+            CategoryLookupEntry::new("NAME", "DM", "drogerie", MatchType::Exact),
+        ];
+        let records = vec![
+            record("REWE Markt", "Einkauf"),
+            record("REWE City", "Bar Einzahlung"),
+            record("DM", ""),
+        ];
+
+        let report = check(&records, &lookup, &categories);
+        assert_eq!(
+            report,
+            Report {
+                conflicts: vec![Conflict {
+                    record: 1,
+                    rules: vec![0, 1]
+                }],
+                shadowed: vec![(1, vec![0]), (2, vec![0])],
+                unmatched: vec![3, 4],
+            }
+        );
+        assert_eq!(
+            format(&report, &records, &lookup, &categories),
+            vec![
+                "# conflicts",
+                "2026-01-02 -12.50€ REWE City - Bar Einzahlung: rule 0 (alltag) wins over rule 1 (ausgehen)",
+                "# shadowed rules",
+                "rule 1 (ausgehen): always decided by rule 0 (alltag)",
+                "rule 2 (alltag): always decided by rule 0 (alltag)",
+                "# rules without match",
+                "rule 3 (ausgehen)",
+                "rule 4 (drogerie): unknown category",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_with_priority() {
+        let categories = vec![category("alltag"), category("ausgehen")];
+        let mut lookup = vec![
+            CategoryLookupEntry::new("NAME", "REWE", "alltag", MatchType::Contains),
+            CategoryLookupEntry::new("REFERENCE", "Bar", "ausgehen", MatchType::Contains),
+        ];
+        lookup[1].priority = 1;
+        let records = vec![record("REWE", "Einkauf"), record("REWE", "Bar")];
+
+        let report = check(&records, &lookup, &categories);
+        assert_eq!(report.conflicts[0].rules, vec![1, 0]);
+        assert!(report.shadowed.is_empty());
+        assert!(report.unmatched.is_empty());
+    }
+}
@@ -1,10 +1,19 @@
+mod check;
+mod migrate;
+mod push;
+mod refund;
 mod rules;
+mod transfer;
 
+use ausgabenzettel_core::{Category, Kind, category};
 use clap::{Parser, Subcommand};
 use rules::{generate_html, load_categories, load_lookup, prompt_for_category};
 use std::fs;
 
-use crate::rules::{CsvRecord, find_category};
+use crate::rules::{
+    CsvRecord, Input, category_totals, encoding::Encoding, find_category, format_totals,
+    importer::Registry, net_cash_flow,
+};
 
 #[derive(Parser)]
 #[command(name = "auseinnahmen")]
@@ -14,51 +23,160 @@ struct Args {
     command: Commands,
 }
 
+/// The bank exports to read and how.
+#[derive(clap::Args)]
+struct InputArgs {
+    /// Bank export as `bank:path`, e.g. `dkb:visa.csv`, the bank is
+    /// detected without a prefix (repeatable)
+    #[arg(long = "input", required = true)]
+    inputs: Vec<Input>,
+
+    /// JSON array of own IBANs, transactions with them are transfers
+    #[arg(long)]
+    own_ibans: Option<String>,
+
+    /// Column mapping JSON describing the export of another bank (repeatable)
+    #[arg(long = "mapping")]
+    mappings: Vec<String>,
+
+    /// Encoding of the exports: utf-8, iso-8859-1 or windows-1252, detected
+    /// by default
+    #[arg(long)]
+    encoding: Option<Encoding>,
+
+    /// Fail on rows of the exports that are not transactions instead of
+    /// skipping them
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(clap::Args)]
+struct RulesArgs {
+    /// Path to category definitions JSON
+    categories: String,
+
+    /// Path to existing category lookup JSON (optional)
+    #[arg(short, long)]
+    lookup: Option<String>,
+
+    /// Path to write output JSON (optional, defaults to stdout)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Override lookup file with output
+    #[arg(short = 'i', long = "in-place")]
+    in_place: bool,
+}
+
+#[derive(Subcommand)]
+enum RulesCommand {
+    /// Report transactions matched by rules of different categories and rules
+    /// that are shadowed or never match
+    Check {
+        /// Path to category definitions JSON
+        categories: String,
+
+        /// Path to category lookup JSON
+        lookup: String,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Process CSV and generate category rules
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Rules {
-        /// Path to banking CSV export
-        csv: String,
+        #[command(subcommand)]
+        command: Option<RulesCommand>,
+
+        #[command(flatten)]
+        args: Option<RulesArgs>,
+
+        // next to the other arguments, clap does not detect nested flattened
+        // ones of optional arguments
+        #[command(flatten)]
+        input: Option<InputArgs>,
+    },
 
+    /// Transform CSV with categories and lookup to HTML
+    Transform {
         /// Path to category definitions JSON
         categories: String,
 
-        /// Path to existing category lookup JSON (optional)
-        #[arg(short, long)]
-        lookup: Option<String>,
+        /// Path to category lookup JSON
+        lookup: String,
 
-        /// Path to write output JSON (optional, defaults to stdout)
+        /// Path to write output HTML (optional, defaults to stdout)
         #[arg(short, long)]
         output: Option<String>,
 
-        /// Override lookup file with output
-        #[arg(short = 'i', long = "in-place")]
-        in_place: bool,
+        /// Drop income and only keep expenses and the refunds linked to them
+        #[arg(long)]
+        expenses_only: bool,
 
-        /// Bank type (gls or dkb)
-        #[arg(short, long, default_value = "gls")]
-        bank: String,
-    },
+        /// Link income to expenses of the same counterparty booked at most this many days before as refunds
+        #[arg(long, default_value_t = 60)]
+        refund_days: i64,
 
-    /// Transform CSV with categories and lookup to HTML
-    Transform {
-        /// Path to banking CSV export
-        csv: String,
+        #[command(flatten)]
+        input: InputArgs,
+    },
 
+    /// Categorize CSV and merge it into a document of the backend
+    Push {
         /// Path to category definitions JSON
         categories: String,
 
         /// Path to category lookup JSON
         lookup: String,
 
-        /// Path to write output HTML (optional, defaults to stdout)
+        /// Url of the document, e.g. https://localhost:3000/2026
         #[arg(short, long)]
-        output: Option<String>,
+        url: String,
+
+        /// Client certificate (PEM) accepted by the backend
+        #[arg(long)]
+        cert: String,
+
+        /// Private key (PEM) of the client certificate
+        #[arg(long)]
+        key: String,
+
+        /// CA certificate (PEM) of the server, defaults to the system roots
+        #[arg(long)]
+        ca: Option<String>,
+
+        /// How often to retry when the document changed while merging
+        #[arg(long, default_value_t = 3)]
+        retries: usize,
+
+        /// Drop income and only keep expenses and the refunds linked to them
+        #[arg(long)]
+        expenses_only: bool,
+
+        /// Link income to expenses of the same counterparty booked at most this many days before as refunds
+        #[arg(long, default_value_t = 60)]
+        refund_days: i64,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+
+    /// Rewrite lookup files and stored documents from category indices to ids
+    Migrate {
+        /// Path to category definitions JSON containing `id` and the former `index`
+        categories: String,
 
-        /// Bank type (gls or dkb)
-        #[arg(short, long, default_value = "gls")]
-        bank: String,
+        /// Lookup JSON to rewrite in place (repeatable)
+        #[arg(short, long)]
+        lookup: Vec<String>,
+
+        /// Stored HTML document to rewrite in place (repeatable)
+        #[arg(short, long)]
+        document: Vec<String>,
     },
 }
 
@@ -67,41 +185,93 @@ fn main() {
 
     match args.command {
         Commands::Rules {
-            csv,
+            command:
+                Some(RulesCommand::Check {
+                    categories,
+                    lookup,
+                    input,
+                }),
+            ..
+        } => {
+            let records = read_records(&input);
+            run_check(&records, &categories, &lookup);
+        }
+        Commands::Rules {
+            command: None,
+            args: Some(args),
+            input: Some(input),
+        } => {
+            let records = read_records(&input);
+            run_rules(
+                &records,
+                &args.categories,
+                args.lookup.as_deref(),
+                args.output.as_deref(),
+                args.in_place,
+            );
+        }
+        Commands::Rules { .. } => unreachable!("clap requires the arguments without subcommand"),
+        Commands::Transform {
             categories,
-            lookup: lookup_arg,
+            lookup,
             output,
-            in_place: override_lookup,
-            bank,
+            expenses_only,
+            refund_days,
+            input,
         } => {
-            run_rules(
-                &csv,
+            let mut records = read_records(&input);
+            link_records(&mut records, refund_days);
+            if expenses_only {
+                records = refund::expenses_and_refunds(records);
+            }
+            run_transform(
+                &records,
                 &categories,
-                lookup_arg.as_deref(),
+                &lookup,
                 output.as_deref(),
-                override_lookup,
-                &bank,
+                expenses_only,
             );
         }
-        Commands::Transform {
-            csv,
+        Commands::Push {
             categories,
             lookup,
-            output,
-            bank,
+            url,
+            cert,
+            key,
+            ca,
+            retries,
+            expenses_only,
+            refund_days,
+            input,
+        } => {
+            let identity = push::Identity {
+                cert: &cert,
+                key: &key,
+                ca: ca.as_deref(),
+            };
+            let mut records = read_records(&input);
+            link_records(&mut records, refund_days);
+            if expenses_only {
+                records = refund::expenses_and_refunds(records);
+            }
+            run_push(&records, &categories, &lookup, &url, &identity, retries);
+        }
+        Commands::Migrate {
+            categories,
+            lookup,
+            document,
         } => {
-            run_transform(&csv, &categories, &lookup, output.as_deref(), &bank);
+            run_migrate(&categories, &lookup, &document);
         }
     }
 }
 
 fn run_rules(
-    csv: &str,
+    records: &[CsvRecord],
     categories: &str,
     lookup_arg: Option<&str>,
     output: Option<&str>,
     override_lookup: bool,
-    bank: &str,
 ) {
     let categories = load_categories(categories).expect("Failed to load categories");
     let mut lookup = match lookup_arg {
@@ -115,21 +285,18 @@ fn run_rules(
         }
     };
 
-    let records = if bank == "dkb" {
-        rules::read_dkb_csv(csv).expect("Failed to read DKB CSV")
-    } else {
-        rules::read_gls_csv(csv).expect("Failed to read CSV")
-    };
-
     eprintln!("# categories");
-    for category in &categories {
+    for (depth, category) in category::tree(&categories) {
         eprintln!(
-            "## [{}] {}\n{}",
-            category.index, category.title, category.description
+            "##{} [{}] {}\n{}",
+            "#".repeat(depth),
+            category.id,
+            category.title,
+            category.description
         );
     }
-    for record in &records {
-        if !find_category(record, &lookup)
+    for record in records.iter().filter(|x| x.kind() != Kind::Transfer) {
+        if !find_category(record, &lookup, &categories)
             && let Some(category) = prompt_for_category(&categories, record)
         {
             lookup.push(category);
@@ -147,22 +314,104 @@ fn run_rules(
     }
 }
 
-fn run_transform(csv: &str, categories: &str, lookup: &str, output: Option<&str>, bank: &str) {
+/// Reads the records of all CSV exports, see [`rules::read_inputs`], and
+/// marks transfers to the own IBANs.
+///
+/// Banks of the mapping files are supported next to the built-in ones. Skipped
+/// rows are listed unless `--strict` makes them fail.
+fn read_records(input: &InputArgs) -> Vec<CsvRecord> {
+    let InputArgs {
+        inputs,
+        own_ibans,
+        mappings,
+        encoding,
+        strict,
+    } = input;
+    let registry = Registry::with_mappings(mappings).expect("Failed to load mappings");
+    let rules::Records {
+        mut records,
+        skipped,
+    } = rules::read_inputs(inputs, &registry, *encoding, *strict).expect("Failed to read CSV");
+    if !skipped.is_empty() {
+        eprintln!(
+            "skipped {} rows, use --strict to fail on them",
+            skipped.len()
+        );
+        for (path, row) in &skipped {
+            eprintln!("{}: line {}: {}", path, row.line, row.reason);
+        }
+    }
+    if let Some(path) = own_ibans {
+        let own_ibans = transfer::load_own_ibans(path).expect("Failed to load own IBANs");
+        transfer::classify(&mut records, &own_ibans);
+    }
+
+    records
+}
+
+/// Pairs transfers and links refunds to their expenses, see
+/// [`transfer::pair`] and [`refund::link`].
+fn link_records(records: &mut [CsvRecord], refund_days: i64) {
+    let transfers = records
+        .iter()
+        .filter(|x| x.kind() == Kind::Transfer)
+        .count();
+    if transfers > 0 {
+        let pairs = transfer::pair(records);
+        eprintln!(
+            "{} transfers between own accounts, {} without counterpart",
+            transfers,
+            transfers - 2 * pairs
+        );
+    }
+    let linked = refund::link(records, refund_days);
+    if linked > 0 {
+        eprintln!("linked {} refunds to their expenses", linked);
+    }
+}
+
+/// Prints the totals per category of the kind, subcategories indented.
+fn print_totals(
+    title: &str,
+    kind: Kind,
+    categories: &[Category],
+    totals: &std::collections::HashMap<(Kind, Option<&str>), rules::Totals>,
+) {
+    eprintln!("# {}", title);
+    for (depth, category) in category::tree(categories) {
+        if let Some(total) = totals.get(&(kind, Some(&category.id as &str))) {
+            eprintln!(
+                "{}{}: {}",
+                "  ".repeat(depth),
+                category.title,
+                format_totals(total)
+            );
+        }
+    }
+    if let Some(total) = totals.get(&(kind, None)) {
+        eprintln!("Unknown: {}", format_totals(total));
+    }
+}
+
+fn run_transform(
+    records: &[CsvRecord],
+    categories: &str,
+    lookup: &str,
+    output: Option<&str>,
+    expenses_only: bool,
+) {
     let categories = load_categories(categories).expect("Failed to load categories");
     let lookup = load_lookup(lookup).expect("Failed to load lookup");
 
-    let records = if bank == "dkb" {
-        rules::read_dkb_csv(csv).expect("Failed to read DKB CSV")
-    } else {
-        rules::read_gls_csv(csv).expect("Failed to read CSV")
-    };
-
-    let filtered_records: Vec<CsvRecord> = records
-        .into_iter()
-        .filter(|r| r.amount.replace(',', "").starts_with('-'))
-        .collect();
+    let html = generate_html(records, &lookup, &categories);
 
-    let html = generate_html(&filtered_records, &lookup, &categories);
+    let totals = category_totals(records, &lookup, &categories);
+    print_totals("expenses", Kind::Expense, &categories, &totals);
+    if !expenses_only {
+        print_totals("income", Kind::Income, &categories, &totals);
+        eprintln!("# net cash flow");
+        eprintln!("{}", format_totals(&net_cash_flow(records)));
+    }
 
     if let Some(path) = output {
         fs::write(path, &html).expect("Failed to write output file");
@@ -170,3 +419,65 @@ fn run_transform(csv: &str, categories: &str, lookup: &str, output: Option<&str>
         println!("{}", html);
     }
 }
+
+fn run_push(
+    records: &[CsvRecord],
+    categories: &str,
+    lookup: &str,
+    url: &str,
+    identity: &push::Identity,
+    retries: usize,
+) {
+    let categories = load_categories(categories).expect("Failed to load categories");
+    let lookup = load_lookup(lookup).expect("Failed to load lookup");
+
+    let expenses = rules::to_expenses(records, &lookup, &categories);
+
+    let agent = push::agent(identity).expect("Failed to load client certificate");
+    match push::push(&agent, url, &expenses, &categories, retries) {
+        Ok(added) => eprintln!(
+            "added {} of {} transactions to {}",
+            added,
+            expenses.len(),
+            url
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the report of `rules check`, exits with 1 if anything was found.
+fn run_check(records: &[CsvRecord], categories: &str, lookup: &str) {
+    let categories = load_categories(categories).expect("Failed to load categories");
+    let lookup = load_lookup(lookup).expect("Failed to load lookup");
+
+    let report = check::check(records, &lookup, &categories);
+    for line in check::format(&report, records, &lookup, &categories) {
+        println!("{}", line);
+    }
+    if !report.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn run_migrate(categories: &str, lookups: &[String], documents: &[String]) {
+    let categories = load_categories(categories).expect("Failed to load categories");
+
+    for path in lookups {
+        let content = fs::read_to_string(path).expect("Failed to read lookup file");
+        let migrated = migrate::migrate_lookup(&content, &categories)
+            .unwrap_or_else(|e| panic!("Failed to migrate {}: {}", path, e));
+        fs::write(path, migrated).expect("Failed to write lookup file");
+        eprintln!("migrated {}", path);
+    }
+
+    for path in documents {
+        let content = fs::read_to_string(path).expect("Failed to read document");
+        let migrated = migrate::migrate_document(&content, &categories)
+            .unwrap_or_else(|e| panic!("Failed to migrate {}: {}", path, e));
+        fs::write(path, migrated).expect("Failed to write document");
+        eprintln!("migrated {}", path);
+    }
+}
@@ -0,0 +1,125 @@
+use ausgabenzettel_core::category::{Category, UNKNOWN_CLASS};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Class the former HTML generation used for unknown categories.
+const LEGACY_UNKNOWN_CLASS: &str = "cat404";
+
+/// Maps the former positional index of each category to its id.
+fn index_map(categories: &[Category]) -> HashMap<usize, &str> {
+    categories
+        .iter()
+        .filter_map(|x| x.index.map(|index| (index, &x.id as &str)))
+        .collect()
+}
+
+/// Rewrites the `category` of each lookup rule from the former index to the id.
+///
+/// Rules already referencing an id are kept as they are.
+pub fn migrate_lookup(content: &str, categories: &[Category]) -> Result<String, String> {
+    let ids = index_map(categories);
+    let mut entries: Vec<Value> =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse lookup JSON: {}", e))?;
+
+    for (i, entry) in entries.iter_mut().enumerate() {
+        let Some(category) = entry.get_mut("category") else {
+            return Err(format!("Rule {} has no category", i));
+        };
+        if let Some(index) = category.as_u64() {
+            let id = ids
+                .get(&(index as usize))
+                .ok_or_else(|| format!("Rule {} references unknown category index {}", i, index))?;
+            *category = Value::String(id.to_string());
+        }
+    }
+
+    Ok(serde_json::to_string_pretty(&entries).unwrap())
+}
+
+fn migrate_class(class: &str, ids: &HashMap<usize, &str>) -> Result<String, String> {
+    if class == LEGACY_UNKNOWN_CLASS {
+        return Ok(UNKNOWN_CLASS.to_string());
+    }
+    match class.strip_prefix("cat").map(|x| x.parse::<usize>()) {
+        Some(Ok(position)) if position > 0 => ids
+            .get(&(position - 1))
+            .map(|id| format!("cat-{}", id))
+            .ok_or_else(|| format!("Class {} references unknown category index", class)),
+        _ => Ok(class.to_string()),
+    }
+}
+
+/// Rewrites classes like `cat1` within a stored document to `cat-{id}`.
+pub fn migrate_document(html: &str, categories: &[Category]) -> Result<String, String> {
+    const ATTRIBUTE: &str = "class=\"";
+    let ids = index_map(categories);
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(ATTRIBUTE) {
+        let (before, after) = rest.split_at(start + ATTRIBUTE.len());
+        result.push_str(before);
+        let end = after
+            .find('"')
+            .ok_or_else(|| "Unterminated class attribute".to_string())?;
+        let classes = after[..end]
+            .split(' ')
+            .map(|x| migrate_class(x, &ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        result.push_str(&classes.join(" "));
+        rest = &after[end..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn categories() -> Vec<Category> {
+        ["alltag", "ausgehen"]
+            .iter()
+            .enumerate()
+            .map(|(index, id)| Category {
+                index: Some(index),
+                ..Category::new(id, id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_migrate_lookup() {
+        let lookup = r#"[
+            {"field": "NAME", "value": "REWE", "category": 0, "match_type": "Contains"},
+            {"field": "NAME", "value": "Bar", "category": "ausgehen", "match_type": "Exact"}
+        ]"#;
+        let migrated = migrate_lookup(lookup, &categories()).unwrap();
+        let entries: Vec<Value> = serde_json::from_str(&migrated).unwrap();
+        assert_eq!(entries[0]["category"], "alltag");
+        assert_eq!(entries[1]["category"], "ausgehen");
+    }
+
+    #[test]
+    fn test_migrate_lookup_unknown_index() {
+        let lookup = r#"[{"field": "NAME", "value": "x", "category": 7, "match_type": "Exact"}]"#;
+        assert!(migrate_lookup(lookup, &categories()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_document() {
+        let html = r#"<div id="details"><details class="cat2" id="1"></details><details class="cat404 hidden" id="2"><div class="row"></div></details></div>"#;
+        let migrated = migrate_document(html, &categories()).unwrap();
+        assert_eq!(
+            migrated,
+            r#"<div id="details"><details class="cat-ausgehen" id="1"></details><details class="cat-unknown hidden" id="2"><div class="row"></div></details></div>"#
+        );
+    }
+
+    #[test]
+    fn test_migrate_document_is_idempotent() {
+        let html = r#"<details class="cat-alltag" id="1"></details>"#;
+        assert_eq!(migrate_document(html, &categories()).unwrap(), html);
+    }
+}
@@ -0,0 +1,316 @@
+use ausgabenzettel_core::{Category, Expense, document};
+use std::fs;
+use ureq::{
+    Agent,
+    http::StatusCode,
+    tls::{Certificate, ClientCert, PemItem, PrivateKey, RootCerts, TlsConfig},
+};
+
+/// PEM files used to authenticate against the backend.
+pub struct Identity<'a> {
+    /// Client certificate signed by the CA the backend trusts.
+    pub cert: &'a str,
+    pub key: &'a str,
+    /// CA of the server certificate, the system roots are used when missing.
+    pub ca: Option<&'a str>,
+}
+
+fn read_certificates(path: &str) -> Result<Vec<Certificate<'static>>, String> {
+    let pem = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let certificates = ureq::tls::parse_pem(&pem)
+        .filter_map(|item| match item {
+            Ok(PemItem::Certificate(x)) => Some(Ok(x)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+    if certificates.is_empty() {
+        return Err(format!("No certificate found in {}", path));
+    }
+    Ok(certificates)
+}
+
+/// Creates an agent authenticating with the client certificate.
+///
+/// HTTP status codes are not treated as errors so that conflicts can be retried.
+pub fn agent(identity: &Identity) -> Result<Agent, String> {
+    let certificates = read_certificates(identity.cert)?;
+    let key = fs::read(identity.key)
+        .map_err(|e| format!("Failed to read {}: {}", identity.key, e))
+        .and_then(|pem| {
+            PrivateKey::from_pem(&pem)
+                .map_err(|e| format!("Failed to parse {}: {}", identity.key, e))
+        })?;
+
+    let mut tls =
+        TlsConfig::builder().client_cert(Some(ClientCert::new_with_certs(&certificates, key)));
+    if let Some(ca) = identity.ca {
+        tls = tls.root_certs(RootCerts::new_with_certs(&read_certificates(ca)?));
+    }
+
+    let config = Agent::config_builder()
+        .tls_config(tls.build())
+        .http_status_as_error(false)
+        .build();
+    Ok(config.into())
+}
+
+/// Returns the url of the stored `#details` content of a document url,
+/// e.g. `https://host/_content/2026` for `https://host/2026`.
+fn content_url(url: &str) -> Result<String, String> {
+    match url.trim_end_matches('/').rsplit_once('/') {
+        Some((base, name))
+            if base.contains("://")
+                && !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            Ok(format!("{}/_content/{}", base, name))
+        }
+        _ => Err(format!(
+            "Invalid document url {}, expected something like https://host/2026",
+            url
+        )),
+    }
+}
+
+fn fetch_etag(agent: &Agent, url: &str) -> Result<String, String> {
+    let response = agent
+        .head(url)
+        .call()
+        .map_err(|e| format!("Failed to request {}: {}", url, e))?;
+    if response.status() != StatusCode::OK {
+        return Err(format!("Failed to request {}: {}", url, response.status()));
+    }
+    response
+        .headers()
+        .get("etag")
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string())
+        .ok_or_else(|| format!("Failed to request {}: no ETag", url))
+}
+
+fn fetch_content(agent: &Agent, url: &str) -> Result<String, String> {
+    let mut response = agent
+        .get(url)
+        .call()
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if response.status() != StatusCode::OK {
+        return Err(format!("Failed to download {}: {}", url, response.status()));
+    }
+    response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| format!("Failed to download {}: {}", url, e))
+}
+
+/// Merges the expenses into the document stored at the url.
+///
+/// Fetches the current ETag, merges into the stored `#details` content and
+/// uploads it with `If-Match`. When the document changed in between, the
+/// merge is retried up to `retries` times. Returns the number of added
+/// expenses.
+pub fn push(
+    agent: &Agent,
+    url: &str,
+    expenses: &[Expense],
+    categories: &[Category],
+    retries: usize,
+) -> Result<usize, String> {
+    let content_url = content_url(url)?;
+
+    for attempt in 0..=retries {
+        let etag = fetch_etag(agent, url)?;
+        let content = fetch_content(agent, &content_url)?;
+        let merged = document::merge(&content, expenses, categories);
+        for warning in &merged.warnings {
+            eprintln!("{}: {}", url, warning);
+        }
+        if merged.added == 0 {
+            return Ok(0);
+        }
+
+        let response = agent
+            .put(url)
+            .header("Content-Type", "text/html")
+            .header("If-Match", &etag)
+            .send(&merged.html)
+            .map_err(|e| format!("Failed to upload {}: {}", url, e))?;
+        match response.status() {
+            StatusCode::OK => return Ok(merged.added),
+            StatusCode::CONFLICT | StatusCode::LOCKED if attempt < retries => {
+                eprintln!(
+                    "{} changed while merging, retrying ({}/{})",
+                    url,
+                    attempt + 1,
+                    retries
+                );
+            }
+            status => return Err(format!("Failed to upload {}: {}", url, status)),
+        }
+    }
+
+    unreachable!("the last attempt either returns or fails")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ausgabenzettel_core::{Kind, Money};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    struct Server {
+        etag: String,
+        content: String,
+        /// Number of uploads to answer with a conflict after changing the content.
+        conflicts: usize,
+    }
+
+    fn entry(id: &str) -> String {
+        format!(
+            "<details class=\"cat-unknown\" id=\"{id}\"><summary><span>x</span><span>1.00€</span></summary></details>"
+        )
+    }
+
+    /// Serves HEAD, GET and PUT similar to the backend without TLS.
+    fn serve(server: Arc<Mutex<Server>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap();
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+                let header = |name: &str| {
+                    headers
+                        .iter()
+                        .find(|(x, _)| x == name)
+                        .map(|(_, x)| x.clone())
+                };
+                let mut body = vec![0; header("content-length").map_or(0, |x| x.parse().unwrap())];
+                reader.read_exact(&mut body).unwrap();
+
+                let mut server = server.lock().unwrap();
+                let (status, response) = match request_line.split(' ').next().unwrap() {
+                    "HEAD" => ("200 OK", String::new()),
+                    "GET" => ("200 OK", server.content.clone()),
+                    "PUT" if header("if-match").as_ref() != Some(&server.etag) => {
+                        ("409 Conflict", String::new())
+                    }
+                    "PUT" if server.conflicts > 0 => {
+                        server.conflicts -= 1;
+                        server.content = server.content.replace("</div>", &entry("9"));
+                        server.content.push_str("</div>");
+                        server.etag.push('x');
+                        ("409 Conflict", String::new())
+                    }
+                    _ => {
+                        server.content = String::from_utf8(body).unwrap();
+                        server.etag.push('y');
+                        ("200 OK", String::new())
+                    }
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    server.etag,
+                    response.len()
+                )
+                .unwrap();
+            }
+        });
+        format!("http://{address}/2026")
+    }
+
+    fn expense(id: &str) -> Expense {
+        Expense {
+            id: id.to_string(),
+            kind: Kind::Expense,
+            booking_date: None,
+            value_date: None,
+            category: None,
+            title: "Kiosk".to_string(),
+            amount: Money::new(100, "EUR"),
+            fingerprint: None,
+            refund_of: None,
+            transfer_pair: None,
+            split_of: None,
+        }
+    }
+
+    fn agent() -> Agent {
+        Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_content_url() {
+        assert_eq!(
+            content_url("https://host:3000/2026/").unwrap(),
+            "https://host:3000/_content/2026"
+        );
+        assert!(content_url("https://host").is_err());
+        assert!(content_url("https://host/_summary/2026").is_ok());
+        assert!(content_url("https://host/20-26").is_err());
+    }
+
+    #[test]
+    fn test_push_retries_on_conflict() {
+        let server = Arc::new(Mutex::new(Server {
+            etag: "A".to_string(),
+            content: format!("<div id=\"details\">{}</div>", entry("1")),
+            conflicts: 1,
+        }));
+        let url = serve(server.clone());
+
+        let added = push(&agent(), &url, &[expense("1"), expense("2")], &[], 3).unwrap();
+
+        assert_eq!(added, 1);
+        let ids: Vec<String> = document::parse(&server.lock().unwrap().content)
+            .expenses
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+        assert_eq!(ids, vec!["1", "9", "2"]);
+    }
+
+    #[test]
+    fn test_push_gives_up_after_retries() {
+        let server = Arc::new(Mutex::new(Server {
+            etag: "A".to_string(),
+            content: "<div id=\"details\"></div>".to_string(),
+            conflicts: 2,
+        }));
+        let url = serve(server);
+
+        assert!(push(&agent(), &url, &[expense("2")], &[], 1).is_err());
+    }
+
+    #[test]
+    fn test_push_without_new_expenses_does_not_upload() {
+        let server = Arc::new(Mutex::new(Server {
+            etag: "A".to_string(),
+            content: format!("<div id=\"details\">{}</div>", entry("1")),
+            conflicts: 0,
+        }));
+        let url = serve(server.clone());
+
+        assert_eq!(push(&agent(), &url, &[expense("1")], &[], 0), Ok(0));
+        assert_eq!(server.lock().unwrap().etag, "A");
+    }
+}
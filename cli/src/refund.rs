@@ -0,0 +1,208 @@
+use crate::rules::{CsvRecord, normalize_iban};
+use ausgabenzettel_core::{Kind, Money};
+use std::io::{self, Write};
+
+/// An income transaction that refunds an earlier expense of the same export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refund {
+    /// Index of the income within the records.
+    pub refund: usize,
+    /// Index of the refunded expense within the records.
+    pub original: usize,
+    /// False for partial amounts or counterparties only matching by name,
+    /// those should be confirmed.
+    pub certain: bool,
+}
+
+fn same_iban(a: &CsvRecord, b: &CsvRecord) -> bool {
+    let iban = normalize_iban(&a.iban);
+    !iban.is_empty() && iban == normalize_iban(&b.iban)
+}
+
+fn same_name(a: &CsvRecord, b: &CsvRecord) -> bool {
+    let name = a.name.trim();
+    !name.is_empty() && name.eq_ignore_ascii_case(b.name.trim())
+}
+
+/// Finds income refunding an expense to the same counterparty booked at most
+/// `days` days before.
+///
+/// A refund may cover the expense partially, several refunds of one expense
+/// never exceed its amount. Expenses refunded in full are preferred, otherwise
+/// the closest one is used.
+pub fn find_refunds(records: &[CsvRecord], days: i64) -> Vec<Refund> {
+    let mut remaining: Vec<Money> = records.iter().map(|x| x.amount.abs()).collect();
+    let mut income: Vec<usize> = (0..records.len())
+        .filter(|&i| records[i].kind() == Kind::Income)
+        .collect();
+    income.sort_by_key(|&i| records[i].booking_date);
+
+    let mut refunds = Vec::new();
+    for i in income {
+        let refund = &records[i];
+        let amount = refund.amount.abs();
+        let best = (0..records.len())
+            .filter(|&j| {
+                let expense = &records[j];
+                let elapsed = (refund.booking_date - expense.booking_date).num_days();
+                expense.kind() == Kind::Expense
+                    && (0..=days).contains(&elapsed)
+                    && remaining[j].currency == amount.currency
+                    && remaining[j].minor >= amount.minor
+                    && (same_iban(refund, expense) || same_name(refund, expense))
+            })
+            .min_by_key(|&j| {
+                (
+                    remaining[j] != amount,
+                    refund.booking_date - records[j].booking_date,
+                )
+            });
+
+        if let Some(j) = best {
+            remaining[j].minor -= amount.minor;
+            refunds.push(Refund {
+                refund: i,
+                original: j,
+                certain: amount == records[j].amount.abs() && same_iban(refund, &records[j]),
+            });
+        }
+    }
+    refunds
+}
+
+/// Asks whether the income refunds the expense, defaults to no.
+pub fn confirm(refund: &CsvRecord, original: &CsvRecord) -> bool {
+    eprintln!();
+    eprintln!("# refund");
+    eprintln!(
+        "{} {}: {}",
+        refund.booking_date,
+        refund.amount,
+        refund.get_title()
+    );
+    eprintln!(
+        "{} {}: {}",
+        original.booking_date,
+        original.amount,
+        original.get_title()
+    );
+    eprint!("Is this a refund of the expense? [y/N]: ");
+    io::stderr().flush().unwrap();
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        return false;
+    }
+    matches!(input.trim(), "y" | "Y" | "yes")
+}
+
+/// Links refunds to their expenses, uncertain ones only after confirmation.
+///
+/// Returns the number of linked refunds.
+pub fn link(records: &mut [CsvRecord], days: i64) -> usize {
+    let mut linked = 0;
+    for refund in find_refunds(records, days) {
+        if refund.certain || confirm(&records[refund.refund], &records[refund.original]) {
+            records[refund.refund].refund_of = Some(refund.original);
+            linked += 1;
+        }
+    }
+    linked
+}
+
+/// Keeps the expenses and the refunds linked to them, for `--expenses-only`.
+///
+/// Call it after [`link`], the links of the kept records are moved to the new
+/// indices.
+pub fn expenses_and_refunds(records: Vec<CsvRecord>) -> Vec<CsvRecord> {
+    let keep = |x: &CsvRecord| x.kind() == Kind::Expense || x.refund_of.is_some();
+    let mut index = Vec::with_capacity(records.len());
+    let mut kept = 0;
+    for record in &records {
+        index.push(keep(record).then_some(kept));
+        kept += usize::from(keep(record));
+    }
+    records
+        .into_iter()
+        .filter(keep)
+        .map(|mut record| {
+            record.refund_of = record.refund_of.and_then(|x| index[x]);
+            record.transfer_pair = record.transfer_pair.and_then(|x| index[x]);
+            record
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn record(day: u32, name: &str, iban: &str, amount: &str) -> CsvRecord {
+        CsvRecord {
+            booking_date: NaiveDate::from_ymd_opt(2026, 2, day).unwrap(),
+            iban: iban.to_string(),
+            ..CsvRecord::test(name, amount)
+        }
+    }
+
+    #[test]
+    fn test_full_refund_is_certain() {
+        let records = vec![
+            record(1, "Shop", "DE11", "-30,00"),
+            record(2, "Shop", "DE11", "-20,00"),
+            record(10, "Shop", "DE11 ", "20,00"),
+        ];
+        assert_eq!(
+            find_refunds(&records, 30),
+            vec![Refund {
+                refund: 2,
+                original: 1,
+                certain: true
+            }]
+        );
+    }
+
+    #[test]
+    fn test_partial_refunds_need_confirmation() {
+        let records = vec![
+            record(1, "Shop", "", "-30,00"),
+            record(12, "SHOP", "", "10,00"),
+            record(5, "Shop", "DE11", "20,00"),
+            record(20, "Shop", "", "5,00"),
+        ];
+        let refunds = find_refunds(&records, 30);
+        let pairs: Vec<(usize, usize, bool)> = refunds
+            .iter()
+            .map(|x| (x.refund, x.original, x.certain))
+            .collect();
+        // the 5,00 would exceed the amount left after both other refunds
+        assert_eq!(pairs, vec![(2, 0, false), (1, 0, false)]);
+    }
+
+    #[test]
+    fn test_no_refund_outside_window_or_of_other_counterparty() {
+        let records = vec![
+            record(1, "Shop", "DE11", "-30,00"),
+            record(20, "Shop", "DE11", "30,00"),
+            record(2, "Other", "DE22", "30,00"),
+            record(3, "Shop", "DE11", "40,00"),
+        ];
+        assert!(find_refunds(&records, 10).is_empty());
+    }
+
+    #[test]
+    fn test_expenses_only_keeps_linked_refunds() {
+        let mut records = vec![
+            record(1, "Arbeitgeber", "DE22", "1.000,00"),
+            record(2, "Shop", "DE11", "-30,00"),
+            record(10, "Shop", "DE11", "30,00"),
+        ];
+        assert_eq!(link(&mut records, 30), 1);
+
+        let records = expenses_and_refunds(records);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].name, "Shop");
+        assert_eq!(records[1].refund_of, Some(0));
+    }
+}
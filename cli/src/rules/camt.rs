@@ -0,0 +1,322 @@
+use crate::rules::CsvRecord;
+use crate::rules::importer::{BankImporter, Import};
+use ausgabenzettel_core::Money;
+use chrono::NaiveDate;
+use quick_xml::{Reader, escape::unescape, events::Event};
+
+/// Transaction details of an entry, batch bookings have several.
+#[derive(Debug, Default)]
+struct Details {
+    amount: Option<String>,
+    credit: Option<bool>,
+    reference: Vec<String>,
+    structured_reference: Option<String>,
+    creditor: Option<String>,
+    creditor_iban: Option<String>,
+    creditor_id: Option<String>,
+    mandate_reference: Option<String>,
+    debtor: Option<String>,
+    debtor_iban: Option<String>,
+}
+
+/// An `Ntry` of a statement.
+#[derive(Debug, Default)]
+struct Entry {
+    line: usize,
+    amount: String,
+    currency: String,
+    credit: bool,
+    status: String,
+    booking_date: Option<String>,
+    value_date: Option<String>,
+    booking_text: Option<String>,
+    details: Vec<Details>,
+}
+
+/// ISO 20022 CAMT.053 bank to customer statement.
+pub struct Camt;
+
+impl BankImporter for Camt {
+    fn name(&self) -> &str {
+        "camt"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        line.contains("urn:iso:std:iso:20022:tech:xsd:camt.053")
+    }
+
+    fn parse(&self, content: &str) -> Result<Import, String> {
+        let mut import = Import::default();
+        for entry in parse_entries(content)? {
+            // pending entries are exported again once booked
+            if entry.status == "PDNG" || entry.status == "INFO" {
+                continue;
+            }
+            import.records.extend(
+                to_records(&entry)
+                    .map_err(|e| format!("Failed to parse line {}: {}", entry.line, e))?,
+            );
+        }
+        Ok(import)
+    }
+}
+
+/// Whether the path ends with the elements.
+fn at(path: &[String], suffix: &[&str]) -> bool {
+    path.len() >= suffix.len() && path[path.len() - suffix.len()..] == *suffix
+}
+
+/// Elements within the innermost open element of the name.
+fn within<'a>(path: &'a [String], name: &str) -> Option<&'a [String]> {
+    path.iter().rposition(|x| x == name).map(|i| &path[i + 1..])
+}
+
+fn parse_entries(content: &str) -> Result<Vec<Entry>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    // escaped text of the innermost element
+    let mut text = String::new();
+    let line = |position: u64| {
+        content.as_bytes()[..position as usize]
+            .iter()
+            .filter(|&&x| x == b'\n')
+            .count()
+            + 1
+    };
+
+    loop {
+        let position = reader.buffer_position();
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                match name.as_str() {
+                    "Ntry" => entries.push(Entry {
+                        line: line(position),
+                        ..Entry::default()
+                    }),
+                    "TxDtls" => {
+                        if let Some(entry) = entries.last_mut() {
+                            entry.details.push(Details::default());
+                        }
+                    }
+                    "Amt" if path.last().is_some_and(|x| x == "Ntry") => {
+                        if let Some(entry) = entries.last_mut() {
+                            entry.currency = e
+                                .try_get_attribute("Ccy")
+                                .ok()
+                                .flatten()
+                                .map(|x| String::from_utf8_lossy(&x.value).to_string())
+                                .unwrap_or_default();
+                        }
+                    }
+                    _ => {}
+                }
+                path.push(name);
+                text.clear();
+            }
+            Ok(Event::Text(e)) => text.push_str(&String::from_utf8_lossy(&e)),
+            Ok(Event::GeneralRef(e)) => {
+                text.push('&');
+                text.push_str(&String::from_utf8_lossy(&e));
+                text.push(';');
+            }
+            Ok(Event::End(_)) => {
+                let value = unescape(&text)
+                    .map(|x| x.trim().to_string())
+                    .unwrap_or_else(|_| text.trim().to_string());
+                text.clear();
+                // elements containing others like Sts in version 8 are empty
+                if let Some(entry) = entries.last_mut().filter(|_| !value.is_empty()) {
+                    if let Some(details) = within(&path, "TxDtls")
+                        && let Some(current) = entry.details.last_mut()
+                    {
+                        read_details(current, details, value);
+                    } else if let Some(inner) = within(&path, "Ntry") {
+                        read_entry(entry, inner, value);
+                    }
+                }
+                path.pop();
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(format!(
+                    "Failed to parse line {}: {}",
+                    line(reader.error_position()),
+                    e
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads an element of an entry outside of the transaction details.
+fn read_entry(entry: &mut Entry, path: &[String], value: String) {
+    if path == ["Amt"] {
+        entry.amount = value;
+    } else if path == ["CdtDbtInd"] {
+        entry.credit = value == "CRDT";
+    } else if path == ["Sts"] || path == ["Sts", "Cd"] {
+        entry.status = value;
+    } else if at(path, &["BookgDt", "Dt"]) || at(path, &["BookgDt", "DtTm"]) {
+        entry.booking_date = Some(value);
+    } else if at(path, &["ValDt", "Dt"]) || at(path, &["ValDt", "DtTm"]) {
+        entry.value_date = Some(value);
+    } else if path == ["AddtlNtryInf"] {
+        entry.booking_text = Some(value);
+    }
+}
+
+/// Reads an element of the transaction details.
+fn read_details(details: &mut Details, path: &[String], value: String) {
+    let party = within(path, "RltdPties").and_then(|x| Some((x.first()?.as_str(), x)));
+    if path == ["Amt"] || path == ["AmtDtls", "TxAmt", "Amt"] {
+        details.amount.get_or_insert(value);
+    } else if path == ["CdtDbtInd"] {
+        details.credit = Some(value == "CRDT");
+    } else if path == ["Refs", "MndtId"] {
+        details.mandate_reference = Some(value);
+    } else if path == ["RmtInf", "Ustrd"] {
+        details.reference.push(value);
+    } else if at(path, &["RmtInf", "Strd", "CdtrRefInf", "Ref"]) {
+        details.structured_reference = Some(value);
+    } else if let Some((party, path)) = party {
+        // e.g. Cdtr/Nm in version 2 and Cdtr/Pty/Nm in version 8
+        let name = path.len() <= 3 && at(path, &["Nm"]);
+        match party {
+            "Cdtr" if name => details.creditor = Some(value),
+            "Cdtr" if at(path, &["PrvtId", "Othr", "Id"]) => details.creditor_id = Some(value),
+            "Dbtr" if name => details.debtor = Some(value),
+            "CdtrAcct" if at(path, &["Id", "IBAN"]) => details.creditor_iban = Some(value),
+            "DbtrAcct" if at(path, &["Id", "IBAN"]) => details.debtor_iban = Some(value),
+            _ => {}
+        }
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    // date times like 2026-01-02T10:00:00+01:00 are cut to the date
+    let date = date.get(..10).unwrap_or(date);
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+fn parse_amount(amount: &str, currency: &str, credit: bool) -> Result<Money, String> {
+    let amount = Money::parse_with(amount, currency, '.', None).map_err(|e| e.to_string())?;
+    Ok(if credit { amount } else { -amount })
+}
+
+/// Records of an entry, one per transaction of batch bookings with amounts.
+fn to_records(entry: &Entry) -> Result<Vec<CsvRecord>, String> {
+    let booking_date = parse_date(
+        entry
+            .booking_date
+            .as_deref()
+            .ok_or("Missing booking date")?,
+    )?;
+    let value_date = entry.value_date.as_deref().map(parse_date).transpose()?;
+    let currency = if entry.currency.is_empty() {
+        "EUR"
+    } else {
+        &entry.currency
+    };
+
+    let batch = entry.details.len() > 1 && entry.details.iter().all(|x| x.amount.is_some());
+    let empty = [Details::default()];
+    let details = if entry.details.is_empty() {
+        &empty[..]
+    } else if batch {
+        &entry.details[..]
+    } else {
+        &entry.details[..1]
+    };
+
+    details
+        .iter()
+        .map(|details| {
+            let credit = details.credit.filter(|_| batch).unwrap_or(entry.credit);
+            let amount = match &details.amount {
+                Some(amount) if batch => amount,
+                _ => &entry.amount,
+            };
+            let (name, iban) = if credit {
+                (&details.debtor, &details.debtor_iban)
+            } else {
+                (&details.creditor, &details.creditor_iban)
+            };
+            let reference = if details.reference.is_empty() {
+                details.structured_reference.clone().unwrap_or_default()
+            } else {
+                details.reference.join(" ")
+            };
+            Ok(CsvRecord {
+                booking_date,
+                value_date,
+                amount: parse_amount(amount, currency, credit)?,
+                reference,
+                iban: iban.clone().unwrap_or_default(),
+                name: name.clone().unwrap_or_default(),
+                booking_text: entry.booking_text.clone().unwrap_or_default(),
+                creditor_id: details.creditor_id.clone().unwrap_or_default(),
+                mandate_reference: details.mandate_reference.clone().unwrap_or_default(),
+                refund_of: None,
+                transfer: false,
+                transfer_pair: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture() {
+        let content = include_str!("../../testdata/camt053.xml");
+        assert!(content.lines().take(3).any(|x| Camt.detect(x)));
+        let import = Camt.parse(content).unwrap();
+        let records = import.records;
+
+        // the pending entry is left out without report, the batch booking is split
+        assert_eq!(records.len(), 4);
+        assert!(import.skipped.is_empty());
+        assert_eq!(
+            records[0].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 2).unwrap()
+        );
+        assert_eq!(records[0].value_date, NaiveDate::from_ymd_opt(2026, 1, 3));
+        assert_eq!(records[0].amount, Money::new(-1250, "EUR"));
+        assert_eq!(records[0].name, "REWE Markt");
+        assert_eq!(records[0].iban, "DE12500105170648489890");
+        assert_eq!(records[0].booking_text, "SEPA-BASISLASTSCHRIFT");
+        assert_eq!(records[0].creditor_id, "DE98ZZZ09999999999");
+        assert_eq!(records[0].mandate_reference, "M-4711");
+        assert_eq!(records[0].reference, "Einkauf Filiale 12 & Pfand");
+
+        assert_eq!(records[1].amount, Money::new(245000, "EUR"));
+        assert_eq!(records[1].name, "Arbeitgeber GmbH");
+        assert_eq!(records[1].iban, "DE27100777770209299700");
+        assert_eq!(records[1].reference, "RF18539007547034");
+
+        let batch: Vec<(i64, &str)> = records[2..]
+            .iter()
+            .map(|x| (x.amount.minor, x.name.as_str()))
+            .collect();
+        assert_eq!(batch, vec![(-3000, "Verein A"), (-2000, "Verein B")]);
+    }
+
+    #[test]
+    fn test_missing_booking_date_has_line_number() {
+        let content = include_str!("../../testdata/camt053.xml")
+            .replace("<BookgDt><Dt>2026-01-02</Dt></BookgDt>", "");
+        let error = Camt.parse(&content).unwrap_err();
+        assert!(
+            error.starts_with("Failed to parse line 12: Missing booking date"),
+            "{}",
+            error
+        );
+    }
+}
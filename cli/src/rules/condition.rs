@@ -0,0 +1,328 @@
+use crate::rules::{CsvRecord, FIELDS, MatchType};
+use ausgabenzettel_core::Money;
+use chrono::{Datelike, NaiveDate, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, de::Error};
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+
+/// Condition of a lookup rule, either a field match as written by the
+/// interactive prompt or a combination of conditions.
+///
+/// ```json
+/// {
+///   "all": [
+///     {"field": "NAME", "value": "REWE", "match_type": "Contains"},
+///     {"amount": {"gt": 100}},
+///     {"any": [{"weekday": ["Sat", "Sun"]}, {"date": {"from": "2026-12-01"}}]}
+///   ]
+/// }
+/// ```
+///
+/// Each object is exactly one condition, unknown keys and keys of different
+/// conditions like `field` next to `amount` are rejected.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Condition {
+    All { all: Vec<Condition> },
+    Any { any: Vec<Condition> },
+    Amount { amount: Comparison },
+    Date { date: DateRange },
+    Weekday { weekday: Vec<Weekday> },
+    Field(FieldMatch),
+}
+
+/// Keys of each kind of condition, a field match needs all of its keys.
+const KEYS: [&[&str]; 6] = [
+    &["all"],
+    &["any"],
+    &["amount"],
+    &["date"],
+    &["weekday"],
+    &["field", "value", "match_type"],
+];
+
+/// Matches a field of the record like `NAME` against the value.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldMatch {
+    pub field: String,
+    pub value: String,
+    pub match_type: MatchType,
+    /// Pattern of `Regex` matches, compiled on first use.
+    #[serde(skip)]
+    regex: OnceLock<Regex>,
+}
+
+/// Bounds of the absolute amount, so that `gt` means more than spent or
+/// received. Amounts are compared in the currency of the record.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Comparison {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ge: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub le: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<Amount>,
+}
+
+/// Booking dates from and to the given days, both inclusive.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DateRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+}
+
+/// An amount in minor units, written as number like `100` or string like
+/// `"12.50"`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "Value", into = "String")]
+pub struct Amount(pub i64);
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Condition::from_map(Map::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Condition {
+    /// The condition of a JSON object, see [`KEYS`].
+    fn from_map(mut map: Map<String, Value>) -> Result<Self, String> {
+        let all_keys = || KEYS.concat().join(", ");
+        if let Some(key) = map.keys().find(|x| !KEYS.concat().contains(&x.as_str())) {
+            return Err(format!(
+                "Unknown condition key '{}', expected one of {}",
+                key,
+                all_keys()
+            ));
+        }
+        let kinds: Vec<&[&str]> = KEYS
+            .into_iter()
+            .filter(|keys| keys.iter().any(|x| map.contains_key(*x)))
+            .collect();
+        let keys = match kinds[..] {
+            [keys] => keys,
+            [] => return Err(format!("Missing condition, expected one of {}", all_keys())),
+            _ => {
+                let names: Vec<&str> = kinds.iter().map(|x| x[0]).collect();
+                return Err(format!(
+                    "Conditions {} cannot be mixed within one object, combine them with all or any",
+                    names.join(" and ")
+                ));
+            }
+        };
+        let mut take = |key: &str| map.remove(key).unwrap_or(Value::Null);
+        let condition = match keys[0] {
+            "all" => serde_json::from_value(take("all")).map(|all| Condition::All { all }),
+            "any" => serde_json::from_value(take("any")).map(|any| Condition::Any { any }),
+            "amount" => {
+                serde_json::from_value(take("amount")).map(|amount| Condition::Amount { amount })
+            }
+            "date" => serde_json::from_value(take("date")).map(|date| Condition::Date { date }),
+            "weekday" => serde_json::from_value(take("weekday"))
+                .map(|weekday| Condition::Weekday { weekday }),
+            _ => serde_json::from_value(Value::Object(map)).map(Condition::Field),
+        };
+        condition.map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<Value> for Amount {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let text = match value {
+            Value::Number(x) => x.to_string(),
+            Value::String(x) => x,
+            x => return Err(format!("Invalid amount {}, expected e.g. 12.50", x)),
+        };
+        Money::parse_with(&text, "", '.', None)
+            .map(|x| Amount(x.minor))
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl From<Amount> for String {
+    fn from(amount: Amount) -> Self {
+        Money::new(amount.0, "").amount()
+    }
+}
+
+impl FieldMatch {
+    pub fn new(field: &str, value: &str, match_type: MatchType) -> Self {
+        FieldMatch {
+            field: field.to_string(),
+            value: value.to_string(),
+            match_type,
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// The compiled value of `Regex` matches.
+    pub fn regex(&self) -> Result<&Regex, String> {
+        if let Some(regex) = self.regex.get() {
+            return Ok(regex);
+        }
+        let regex = Regex::new(&self.value)
+            .map_err(|e| format!("Invalid regex '{}': {}", self.value, e))?;
+        Ok(self.regex.get_or_init(|| regex))
+    }
+
+    pub fn matches(&self, record: &CsvRecord) -> bool {
+        let expect_value = record
+            .field(&self.field)
+            .unwrap_or_else(|| panic!("Unknown field: {}", self.field));
+        match self.match_type {
+            MatchType::Exact => self.value == expect_value,
+            MatchType::Contains => expect_value
+                .to_uppercase()
+                .contains(&self.value.to_uppercase()),
+            MatchType::Regex => self
+                .regex()
+                .unwrap_or_else(|e| panic!("{}", e))
+                .is_match(expect_value),
+        }
+    }
+}
+
+impl Comparison {
+    pub fn matches(&self, amount: &Money) -> bool {
+        let amount = amount.minor.abs();
+        self.gt.is_none_or(|x| amount > x.0)
+            && self.ge.is_none_or(|x| amount >= x.0)
+            && self.lt.is_none_or(|x| amount < x.0)
+            && self.le.is_none_or(|x| amount <= x.0)
+            && self.eq.is_none_or(|x| amount == x.0)
+    }
+}
+
+impl DateRange {
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|x| date >= x) && self.to.is_none_or(|x| date <= x)
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, record: &CsvRecord) -> bool {
+        match self {
+            Condition::All { all } => all.iter().all(|x| x.matches(record)),
+            Condition::Any { any } => any.iter().any(|x| x.matches(record)),
+            Condition::Amount { amount } => amount.matches(&record.amount),
+            Condition::Date { date } => date.matches(record.booking_date),
+            Condition::Weekday { weekday } => weekday.contains(&record.booking_date.weekday()),
+            Condition::Field(field) => field.matches(record),
+        }
+    }
+
+    /// Checks the fields and compiles the regular expressions, so that
+    /// matching cannot fail.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Condition::All { all: conditions } | Condition::Any { any: conditions } => {
+                conditions.iter().try_for_each(|x| x.validate())
+            }
+            Condition::Field(field) => {
+                if !FIELDS.contains(&field.field.to_uppercase().as_str()) {
+                    return Err(format!(
+                        "Unknown field {}, expected one of {}",
+                        field.field,
+                        FIELDS.join(", ")
+                    ));
+                }
+                if let MatchType::Regex = field.match_type {
+                    field.regex()?;
+                }
+                Ok(())
+            }
+            Condition::Amount { .. } | Condition::Date { .. } | Condition::Weekday { .. } => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, amount: i64, date: NaiveDate) -> CsvRecord {
+        CsvRecord {
+            booking_date: date,
+            amount: Money::new(amount, "EUR"),
+            ..CsvRecord::test(name, "0")
+        }
+    }
+
+    #[test]
+    fn test_compound_condition() {
+        let condition: Condition = serde_json::from_str(
+            r#"{"all": [
+                {"field": "NAME", "value": "rewe", "match_type": "Contains"},
+                {"amount": {"gt": 100, "le": "250.50"}},
+                {"any": [{"weekday": ["Sat", "sunday"]}, {"date": {"from": "2026-12-01"}}]}
+            ]}"#,
+        )
+        .unwrap();
+        condition.validate().unwrap();
+
+        // 2026-01-03 is a Saturday
+        let saturday = NaiveDate::from_ymd_opt(2026, 1, 3).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let december = NaiveDate::from_ymd_opt(2026, 12, 7).unwrap();
+        assert!(condition.matches(&record("REWE Markt", -15000, saturday)));
+        assert!(condition.matches(&record("REWE Markt", -25050, december)));
+        assert!(!condition.matches(&record("REWE Markt", -15000, monday)));
+        assert!(!condition.matches(&record("REWE Markt", -10000, saturday)));
+        assert!(!condition.matches(&record("REWE Markt", -25051, saturday)));
+        assert!(!condition.matches(&record("DM", -15000, saturday)));
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let json = r#"{"all":[{"amount":{"gt":"100.00"}},{"date":{"to":"2026-01-31"}},{"weekday":["Mon"]},{"field":"IBAN","value":"DE11","match_type":"Exact"}]}"#;
+        let condition: Condition = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&condition).unwrap(), json);
+    }
+
+    #[test]
+    fn test_validate() {
+        let condition = |json: &str| serde_json::from_str::<Condition>(json).unwrap().validate();
+        assert!(
+            condition(r#"{"any": [{"field": "PAYEE", "value": "x", "match_type": "Exact"}]}"#)
+                .unwrap_err()
+                .starts_with("Unknown field PAYEE")
+        );
+        assert!(
+            condition(r#"{"all": [{"field": "NAME", "value": "(", "match_type": "Regex"}]}"#)
+                .is_err()
+        );
+        assert!(serde_json::from_str::<Condition>(r#"{"amount": {"gte": 1}}"#).is_err());
+    }
+
+    #[test]
+    fn test_reject_mixed_or_unknown_keys() {
+        let error = |json: &str| {
+            serde_json::from_str::<Condition>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(
+            error(r#"{"field": "NAME", "match": "REWE", "amount": {"lt": 50}}"#)
+                .starts_with("Unknown condition key 'match'")
+        );
+        assert!(
+            error(
+                r#"{"field": "NAME", "value": "REWE", "match_type": "Exact", "amount": {"lt": 50}}"#
+            )
+            .starts_with("Conditions amount and field cannot be mixed")
+        );
+        assert!(error(r#"{"all": [{"weekday": ["Mon"], "date": {}}]}"#).starts_with("Conditions"));
+        assert!(error(r#"{}"#).starts_with("Missing condition"));
+        assert!(error(r#"{"field": "NAME", "value": "REWE"}"#).contains("match_type"));
+    }
+}
@@ -0,0 +1,135 @@
+use std::mem;
+
+/// A record of a CSV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// Line the record starts on, starting at 1.
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// Removes the byte order mark and unifies line endings.
+pub fn normalize(content: &str) -> String {
+    content
+        .trim_start_matches('\u{FEFF}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+}
+
+/// Parses CSV as described in RFC 4180.
+///
+/// Quoted fields may contain the delimiter, line breaks and doubled quotes.
+/// Quotes within unquoted fields are kept, since some banks do not escape
+/// them. Without `quote` every character is taken as it is.
+pub fn parse(content: &str, delimiter: char, quote: Option<char>) -> Result<Vec<Row>, String> {
+    let content = normalize(content);
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    // whether the current field has content or was quoted
+    let mut started = false;
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if Some(c) == quote {
+                if chars.peek() == Some(&c) {
+                    field.push(c);
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        } else if Some(c) == quote && !started {
+            quoted = true;
+            started = true;
+        } else if c == delimiter {
+            fields.push(mem::take(&mut field));
+            started = false;
+        } else if c == '\n' {
+            fields.push(mem::take(&mut field));
+            rows.push(Row {
+                line: start,
+                fields: mem::take(&mut fields),
+            });
+            started = false;
+            line += 1;
+            start = line;
+        } else {
+            field.push(c);
+            started = true;
+        }
+    }
+
+    if quoted {
+        return Err(format!("Unterminated quote in line {}", start));
+    }
+    if started || !fields.is_empty() {
+        fields.push(field);
+        rows.push(Row {
+            line: start,
+            fields,
+        });
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(content: &str) -> Vec<Vec<String>> {
+        parse(content, ';', Some('"'))
+            .unwrap()
+            .into_iter()
+            .map(|x| x.fields)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            fields("a;\"b;c\";\"say \"\"hi\"\"\"\r\n;\"\"\n"),
+            vec![vec!["a", "b;c", "say \"hi\""], vec!["", ""]]
+        );
+        // quotes within unquoted fields are kept
+        assert_eq!(
+            fields("Brot \"gross\";1"),
+            vec![vec!["Brot \"gross\"", "1"]]
+        );
+        assert_eq!(
+            fields("\u{FEFF}a\n\nb"),
+            vec![vec!["a"], vec![""], vec!["b"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_multiline() {
+        let rows = parse("h\n\"a\nb\";c\nd", ';', Some('"')).unwrap();
+        let lines: Vec<usize> = rows.iter().map(|x| x.line).collect();
+        assert_eq!(lines, vec![1, 2, 4]);
+        assert_eq!(rows[1].fields, vec!["a\nb", "c"]);
+    }
+
+    #[test]
+    fn test_parse_without_quote() {
+        let rows = parse("\"a;b\"", ';', None).unwrap();
+        assert_eq!(rows[0].fields, vec!["\"a", "b\""]);
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        assert_eq!(
+            parse("a\n\"b;c\nd", ';', Some('"')),
+            Err("Unterminated quote in line 2".to_string())
+        );
+    }
+}
@@ -1,40 +1,162 @@
-use crate::rules::CsvRecord;
-use std::fs;
+use crate::rules::csv;
+use crate::rules::importer::{BankImporter, Import, Skipped, find_header, is_header, positions};
+use crate::rules::{CsvRecord, parse_date, parse_optional_date};
+use ausgabenzettel_core::Money;
 
-pub fn read_csv(path: &str) -> Result<Vec<CsvRecord>, String> {
-    let mut content =
-        fs::read_to_string(path).map_err(|e| format!("Failed to read CSV file: {}", e))?;
+/// Columns read from an export, the counterparty is the payer of incoming
+/// and the payee of outgoing payments.
+const COLUMNS: [&str; 7] = [
+    "Buchungsdatum",
+    "Wertstellung",
+    "Zahlungspflichtige*r",
+    "Zahlungsempfänger*in",
+    "IBAN",
+    "Verwendungszweck",
+    "Betrag (€)",
+];
 
-    content = content
-        .trim_start_matches('\u{FEFF}')
-        .replace("\r\n", "\n")
-        .replace('\r', "\n");
+/// Columns missing in older exports: booking state, booking type, creditor ID
+/// and mandate reference.
+const OPTIONAL_COLUMNS: [&str; 4] = ["Status", "Umsatztyp", "Gläubiger-ID", "Mandatsreferenz"];
 
-    let mut records = Vec::new();
-    let lines = content.lines().skip(1);
+/// State of booked transactions, pending ones are `Vorgemerkt`.
+const BOOKED: &str = "Gebucht";
 
-    for line in lines {
-        if line.trim().is_empty() {
-            continue;
-        }
+/// DKB CSV export, account details precede the header row.
+pub struct Dkb;
 
-        let fields: Vec<&str> = line.split(';').collect();
-        if fields.len() < 12 {
-            continue;
-        }
+impl BankImporter for Dkb {
+    fn name(&self) -> &'static str {
+        "dkb"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        is_header(line, ';', Some('"'), &COLUMNS)
+    }
 
-        if fields[4].contains("Zahlungsempfänger") {
-            continue;
+    fn parse(&self, content: &str) -> Result<Import, String> {
+        let rows = csv::parse(content, ';', Some('"'))?;
+        let (
+            header,
+            [
+                booking_date,
+                value_date,
+                payer,
+                payee,
+                iban,
+                reference,
+                amount,
+            ],
+        ) = find_header(&rows, &COLUMNS)?;
+        let [status, booking_text, creditor_id, mandate_reference] =
+            OPTIONAL_COLUMNS.map(|column| positions(&rows[header].fields, &[column]).map(|[x]| x));
+        let width = booking_date
+            .max(value_date)
+            .max(payer)
+            .max(payee)
+            .max(iban)
+            .max(reference)
+            .max(amount)
+            + 1;
+
+        let mut import = Import::default();
+        for row in &rows[header + 1..] {
+            let fields = &row.fields;
+            if fields.len() < width {
+                import.skipped.extend(Skipped::short_row(row, width));
+                continue;
+            }
+
+            let text = |x: Option<usize>| x.and_then(|x| fields.get(x)).map_or("", |x| x.trim());
+            // pending bookings are exported again once booked, with other data
+            if status.is_some() && text(status) != BOOKED {
+                continue;
+            }
+            let amount = Money::parse_german(&fields[amount], "EUR")
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            let booking_date = parse_date(&fields[booking_date])
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+            let value_date = parse_optional_date(&fields[value_date])
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            let name = if amount.is_negative() { payee } else { payer };
+            import.records.push(CsvRecord {
+                booking_date,
+                value_date,
+                amount,
+                reference: fields[reference].clone(),
+                iban: fields[iban].clone(),
+                name: fields[name].clone(),
+                booking_text: text(booking_text).to_string(),
+                creditor_id: text(creditor_id).to_string(),
+                mandate_reference: text(mandate_reference).to_string(),
+                refund_of: None,
+                transfer: false,
+                transfer_pair: None,
+            });
         }
 
-        records.push(CsvRecord {
-            date: Some(fields[0].trim_matches('"').to_string()),
-            amount: fields[8].trim_matches('"').to_string(),
-            reference: fields[5].trim_matches('"').to_string(),
-            iban: fields[7].trim_matches('"').to_string(),
-            name: fields[4].trim_matches('"').to_string(),
-        });
+        Ok(import)
     }
+}
 
-    Ok(records)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_fixture() {
+        let import = Dkb.parse(include_str!("../../testdata/dkb.csv")).unwrap();
+        let records = import.records;
+
+        // the pending booking is left out without report
+        assert_eq!(records.len(), 2);
+        assert!(import.skipped.is_empty());
+        assert_eq!(
+            records[0].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()
+        );
+        assert_eq!(records[0].value_date, NaiveDate::from_ymd_opt(2026, 1, 5));
+        assert_eq!(records[0].name, "Stadtwerke");
+        assert_eq!(records[0].iban, "DE89370400440532013000");
+        assert_eq!(records[0].reference, "Abschlag Strom");
+        assert_eq!(records[0].booking_text, "Ausgang");
+        assert_eq!(records[0].creditor_id, "DE11ZZZ00000012345");
+        assert_eq!(records[0].mandate_reference, "SW-0815");
+        assert_eq!(
+            records[0].amount,
+            Money::parse_german("-85,00", "EUR").unwrap()
+        );
+        assert_eq!(
+            records[1].amount,
+            Money::parse_german("1.200,00", "EUR").unwrap()
+        );
+        assert_eq!(records[1].name, "Arbeitgeber GmbH");
+    }
+
+    #[test]
+    fn test_parse_quoted_fields() {
+        let content = include_str!("../../testdata/dkb_quoted.csv");
+        let records = Dkb.parse(content).unwrap().records;
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].name, "Kiosk; am Markt");
+        assert_eq!(records[0].reference, "Zeitung; Kaffee");
+        assert_eq!(records[0].amount, Money::new(-420, "EUR"));
+        assert_eq!(records[1].reference, "Bestellung 4711\nRechnung \"0815\"");
+        assert_eq!(records[2].amount, Money::new(-8500, "EUR"));
+
+        // the record after the multiline one starts on line 7
+        let error = Dkb.parse(&content.replace("-85,00", "x")).unwrap_err();
+        assert!(error.starts_with("Failed to parse line 7:"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_error_has_line_number() {
+        let content = include_str!("../../testdata/dkb.csv").replace("-85,00", "x");
+        let error = Dkb.parse(&content).unwrap_err();
+        assert!(error.starts_with("Failed to parse line 6:"), "{}", error);
+    }
 }
@@ -0,0 +1,138 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Windows-1252 characters of the bytes 0x80 to 0x9F, the undefined ones are
+/// never decoded.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Character encoding of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Windows1252,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "iso-8859-1" | "latin1" => Ok(Encoding::Latin1),
+            "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+            _ => Err(format!(
+                "Unknown encoding {}, expected utf-8, iso-8859-1 or windows-1252",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Encoding {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Latin1 => "iso-8859-1",
+            Encoding::Windows1252 => "windows-1252",
+        })
+    }
+}
+
+impl Encoding {
+    /// UTF-8 if the bytes are valid UTF-8, otherwise Windows-1252, which
+    /// equals ISO-8859-1 for all printable characters of the latter.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Windows1252
+        }
+    }
+
+    /// Decodes the bytes, control characters of single byte encodings are
+    /// taken as a sign of the wrong encoding.
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, String> {
+        let invalid = |position: usize| {
+            let line = bytes[..position].iter().filter(|&&x| x == b'\n').count() + 1;
+            format!(
+                "Line {} is not valid {}, set another --encoding",
+                line, self
+            )
+        };
+        match self {
+            Encoding::Utf8 => {
+                String::from_utf8(bytes.to_vec()).map_err(|e| invalid(e.utf8_error().valid_up_to()))
+            }
+            Encoding::Latin1 => bytes
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| match x {
+                    0x80..=0x9F => Err(invalid(i)),
+                    _ => Ok(x as char),
+                })
+                .collect(),
+            Encoding::Windows1252 => bytes
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| match x {
+                    0x81 | 0x8D | 0x8F | 0x90 | 0x9D => Err(invalid(i)),
+                    0x80..=0x9F => Ok(WINDOWS_1252[x as usize - 0x80]),
+                    _ => Ok(x as char),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let bytes = b"Betrag (\x80);W\xe4hrung";
+        assert_eq!(
+            Encoding::Windows1252.decode(bytes),
+            Ok("Betrag (€);Währung".to_string())
+        );
+        assert_eq!(
+            Encoding::Latin1.decode(b"W\xe4hrung"),
+            Ok("Währung".to_string())
+        );
+        assert_eq!("CP1252".parse(), Ok(Encoding::Windows1252));
+    }
+
+    #[test]
+    fn test_decode_errors_have_line_number() {
+        let bytes = b"Betrag\n1,00\nW\xe4hrung (\x80)";
+        assert_eq!(
+            Encoding::Utf8.decode(bytes),
+            Err("Line 3 is not valid utf-8, set another --encoding".to_string())
+        );
+        assert_eq!(
+            Encoding::Latin1.decode(bytes),
+            Err("Line 3 is not valid iso-8859-1, set another --encoding".to_string())
+        );
+        assert!(Encoding::Windows1252.decode(b"\n\x81").is_err());
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Encoding::detect("Währung".as_bytes()), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"W\xe4hrung"), Encoding::Windows1252);
+    }
+}
@@ -1,32 +1,171 @@
-use crate::rules::CsvRecord;
-use std::fs;
+use crate::rules::csv;
+use crate::rules::importer::{BankImporter, Import, Skipped, find_header, is_header, positions};
+use crate::rules::{CsvRecord, parse_date, parse_optional_date};
+use ausgabenzettel_core::Money;
 
-pub fn read_csv(path: &str) -> Result<Vec<CsvRecord>, String> {
-    let mut content =
-        fs::read_to_string(path).map_err(|e| format!("Failed to read CSV file: {}", e))?;
+/// Columns read from an export, see [`OPTIONAL_COLUMNS`] for the others.
+const COLUMNS: [&str; 6] = [
+    "Buchungstag",
+    "Valutadatum",
+    "Name Zahlungsbeteiligter",
+    "IBAN Zahlungsbeteiligter",
+    "Verwendungszweck",
+    "Betrag",
+];
 
-    content = content
-        .trim_start_matches('\u{FEFF}')
-        .replace("\r\n", "\n")
-        .replace('\r', "\n");
+/// Columns missing in older exports.
+const OPTIONAL_COLUMNS: [&str; 4] = [
+    "Waehrung",
+    "Buchungstext",
+    "Glaeubiger ID",
+    "Mandatsreferenz",
+];
 
-    let mut records = Vec::new();
-    let lines = content.lines().skip(1);
+/// GLS Gemeinschaftsbank CSV export.
+pub struct Gls;
 
-    for line in lines {
-        let fields: Vec<&str> = line.split(';').collect();
-        if fields.len() < 12 {
-            continue;
+impl BankImporter for Gls {
+    fn name(&self) -> &'static str {
+        "gls"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        is_header(line, ';', Some('"'), &COLUMNS)
+    }
+
+    fn parse(&self, content: &str) -> Result<Import, String> {
+        let rows = csv::parse(content, ';', Some('"'))?;
+        let (header, [booking_date, value_date, name, iban, reference, amount]) =
+            find_header(&rows, &COLUMNS)?;
+        let [currency, booking_text, creditor_id, mandate_reference] =
+            OPTIONAL_COLUMNS.map(|column| positions(&rows[header].fields, &[column]).map(|[x]| x));
+        let width = booking_date
+            .max(value_date)
+            .max(name)
+            .max(iban)
+            .max(reference)
+            .max(amount)
+            + 1;
+
+        let mut import = Import::default();
+        for row in &rows[header + 1..] {
+            let fields = &row.fields;
+            if fields.len() < width {
+                import.skipped.extend(Skipped::short_row(row, width));
+                continue;
+            }
+
+            let text = |x: Option<usize>| x.and_then(|x| fields.get(x)).map_or("", |x| x.trim());
+            let currency = Some(text(currency))
+                .filter(|x| !x.is_empty())
+                .unwrap_or("EUR");
+            let amount = Money::parse_german(&fields[amount], currency)
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            let booking_date = parse_date(&fields[booking_date])
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+            let value_date = parse_optional_date(&fields[value_date])
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            import.records.push(CsvRecord {
+                booking_date,
+                value_date,
+                amount,
+                reference: fields[reference].clone(),
+                iban: fields[iban].clone(),
+                name: fields[name].clone(),
+                booking_text: text(booking_text).to_string(),
+                creditor_id: text(creditor_id).to_string(),
+                mandate_reference: text(mandate_reference).to_string(),
+                refund_of: None,
+                transfer: false,
+                transfer_pair: None,
+            });
         }
 
-        records.push(CsvRecord {
-            date: Some(fields[4].to_string()),
-            amount: fields[11].to_string(),
-            reference: fields[10].to_string(),
-            iban: fields[7].to_string(),
-            name: fields[6].to_string(),
-        });
+        Ok(import)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_fixture() {
+        let records = Gls
+            .parse(include_str!("../../testdata/gls.csv"))
+            .unwrap()
+            .records;
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 2).unwrap()
+        );
+        assert_eq!(records[0].value_date, NaiveDate::from_ymd_opt(2026, 1, 3));
+        assert_eq!(records[0].name, "REWE Markt");
+        assert_eq!(records[0].iban, "DE12500105170648489890");
+        assert_eq!(records[0].reference, "Einkauf");
+        assert_eq!(records[0].booking_text, "Lastschrift");
+        assert_eq!(records[0].creditor_id, "DE98ZZZ09999999999");
+        assert_eq!(records[0].mandate_reference, "M-4711");
+        assert_eq!(
+            records[0].amount,
+            Money::parse_german("-12,50", "EUR").unwrap()
+        );
+        assert_eq!(
+            records[1].amount,
+            Money::parse_german("2.450,00", "EUR").unwrap()
+        );
+        assert_eq!(records[2].value_date, None);
+        assert_eq!(records[2].amount.currency, "USD");
+    }
+
+    #[test]
+    fn test_parse_quoted_fields() {
+        let records = Gls
+            .parse(include_str!("../../testdata/gls_quoted.csv"))
+            .unwrap()
+            .records;
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].name, "Hausverwaltung; Schmidt");
+        assert_eq!(records[0].reference, "Miete; Januar; Whg 3");
+        assert_eq!(records[0].amount, Money::new(-80000, "EUR"));
+        assert_eq!(records[1].reference, "Bestellung 4711\nRechnung 0815");
+        assert_eq!(records[1].amount, Money::new(-4990, "EUR"));
+        assert_eq!(records[2].reference, "Buch \"Momo\"");
+        assert_eq!(
+            records[2].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 7).unwrap()
+        );
     }
 
-    Ok(records)
+    #[test]
+    fn test_parse_reports_short_rows() {
+        let content = format!(
+            "{}\n\n;;Kontostand;3.417,50\n",
+            include_str!("../../testdata/gls.csv").trim_end()
+        );
+        let import = Gls.parse(&content).unwrap();
+
+        // the blank line is left out without report
+        assert_eq!(import.records.len(), 3);
+        assert_eq!(
+            import.skipped,
+            vec![Skipped {
+                line: 6,
+                reason: "4 fields, expected at least 12".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_has_line_number() {
+        let content = include_str!("../../testdata/gls.csv").replace("02.01.2026", "2026-01-02");
+        let error = Gls.parse(&content).unwrap_err();
+        assert!(error.starts_with("Failed to parse line 2:"), "{}", error);
+    }
 }
@@ -0,0 +1,265 @@
+use crate::rules::csv::{self, Row, normalize};
+use crate::rules::encoding::Encoding;
+use crate::rules::mapping::Mapping;
+use crate::rules::{CsvRecord, camt::Camt, dkb::Dkb, gls::Gls, mt940::Mt940};
+
+/// Lines searched for the header row, some banks put account details before it.
+const HEADER_LINES: usize = 10;
+
+/// A row of an export that was left out, e.g. because of missing fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Skipped {
+    /// A row with fewer fields than `width`, None for blank rows.
+    pub fn short_row(row: &Row, width: usize) -> Option<Skipped> {
+        (!row.fields.iter().all(|x| x.trim().is_empty())).then(|| Skipped {
+            line: row.line,
+            reason: format!("{} fields, expected at least {}", row.fields.len(), width),
+        })
+    }
+}
+
+/// The records of an export and the rows that were left out.
+#[derive(Debug, Default)]
+pub struct Import {
+    pub records: Vec<CsvRecord>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Reads the CSV export of one bank.
+pub trait BankImporter {
+    /// Name used as input prefix, e.g. `gls` for `gls:giro.csv`.
+    fn name(&self) -> &str;
+
+    /// Encoding of the exports, None to detect it.
+    fn encoding(&self) -> Option<Encoding> {
+        None
+    }
+
+    /// Decodes an export with the given encoding, otherwise the one of the
+    /// importer or the detected one.
+    fn decode(&self, bytes: &[u8], encoding: Option<Encoding>) -> Result<String, String> {
+        encoding
+            .or(self.encoding())
+            .unwrap_or_else(|| Encoding::detect(bytes))
+            .decode(bytes)
+    }
+
+    /// Whether the line is the header row of an export of this bank.
+    fn detect(&self, line: &str) -> bool;
+
+    /// Parses the content of an export, rows that are not transactions are
+    /// reported as skipped. Blank rows and pending bookings are left out
+    /// without report.
+    fn parse(&self, content: &str) -> Result<Import, String>;
+}
+
+/// The supported banks, detection tries them in the order they were added.
+pub struct Registry {
+    importers: Vec<Box<dyn BankImporter>>,
+}
+
+impl Default for Registry {
+    /// The built-in importers.
+    fn default() -> Self {
+        Registry {
+            importers: vec![
+                Box::new(Gls),
+                Box::new(Dkb),
+                Box::new(Camt),
+                Box::new(Mt940),
+            ],
+        }
+    }
+}
+
+impl Registry {
+    /// The built-in importers and those of the mapping files.
+    pub fn with_mappings(paths: &[String]) -> Result<Self, String> {
+        let mut registry = Registry::default();
+        for path in paths {
+            let mapping = Mapping::load(path).map_err(|e| format!("{}: {}", path, e))?;
+            registry.add(Box::new(mapping))?;
+        }
+        Ok(registry)
+    }
+
+    pub fn add(&mut self, importer: Box<dyn BankImporter>) -> Result<(), String> {
+        if self.importers.iter().any(|x| x.name() == importer.name()) {
+            return Err(format!("Bank {} is defined twice", importer.name()));
+        }
+        self.importers.push(importer);
+        Ok(())
+    }
+
+    /// Names of all supported banks.
+    pub fn names(&self) -> Vec<&str> {
+        self.importers.iter().map(|x| x.name()).collect()
+    }
+
+    /// Returns the importer with the name.
+    pub fn find(&self, name: &str) -> Result<&dyn BankImporter, String> {
+        self.importers
+            .iter()
+            .find(|x| x.name() == name)
+            .map(|x| x.as_ref())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown bank {}, supported banks: {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })
+    }
+
+    /// Returns the importer whose header row is found within the first lines,
+    /// see [`BankImporter::decode`] for the `encoding`.
+    pub fn detect(
+        &self,
+        bytes: &[u8],
+        encoding: Option<Encoding>,
+    ) -> Result<&dyn BankImporter, String> {
+        // reported instead of the unknown format, the header may be fine
+        let mut invalid = None;
+        for importer in &self.importers {
+            match importer.decode(bytes, encoding) {
+                Ok(content) => {
+                    let content = normalize(&content);
+                    let mut lines = content.lines().take(HEADER_LINES);
+                    if lines.any(|line| importer.detect(line)) {
+                        return Ok(importer.as_ref());
+                    }
+                }
+                Err(e) => {
+                    invalid.get_or_insert(e);
+                }
+            }
+        }
+        Err(invalid.unwrap_or_else(|| {
+            format!(
+                "Unknown CSV format, supported banks: {}",
+                self.names().join(", ")
+            )
+        }))
+    }
+}
+
+/// Positions of the columns within the fields of a header row.
+pub fn positions<const N: usize>(fields: &[String], columns: &[&str; N]) -> Option<[usize; N]> {
+    let mut positions = [0; N];
+    for (position, column) in positions.iter_mut().zip(columns) {
+        *position = fields.iter().position(|x| x.trim() == *column)?;
+    }
+    Some(positions)
+}
+
+/// Whether the line is a header row containing all columns.
+pub fn is_header(line: &str, delimiter: char, quote: Option<char>, columns: &[&str]) -> bool {
+    csv::parse(line, delimiter, quote).is_ok_and(|rows| {
+        rows.first().is_some_and(|row| {
+            columns
+                .iter()
+                .all(|column| row.fields.iter().any(|x| x.trim() == *column))
+        })
+    })
+}
+
+/// Returns the index of the header row within the first rows and the
+/// positions of the columns.
+pub fn find_header<const N: usize>(
+    rows: &[Row],
+    columns: &[&str; N],
+) -> Result<(usize, [usize; N]), String> {
+    rows.iter()
+        .take(HEADER_LINES)
+        .enumerate()
+        .find_map(|(i, row)| positions(&row.fields, columns).map(|x| (i, x)))
+        .ok_or_else(|| format!("Missing header row with columns {}", columns.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let registry = Registry::default();
+        let gls = include_str!("../../testdata/gls.csv");
+        let dkb = include_str!("../../testdata/dkb.csv");
+        assert_eq!(
+            registry.detect(gls.as_bytes(), None).map(|x| x.name()),
+            Ok("gls")
+        );
+        assert_eq!(
+            registry.detect(dkb.as_bytes(), None).map(|x| x.name()),
+            Ok("dkb")
+        );
+
+        // same columns with another delimiter
+        let error = registry
+            .detect(gls.replace(';', ",").as_bytes(), None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            "Unknown CSV format, supported banks: gls, dkb, camt, mt940"
+        );
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let registry = Registry::default();
+        let dkb = include_bytes!("../../testdata/dkb_windows1252.csv");
+        assert_eq!(registry.detect(dkb, None).map(|x| x.name()), Ok("dkb"));
+        assert_eq!(
+            registry.detect(dkb, Some(Encoding::Utf8)).err().unwrap(),
+            "Line 3 is not valid utf-8, set another --encoding"
+        );
+    }
+
+    #[test]
+    fn test_registry_with_mappings() {
+        let registry = Registry::with_mappings(&["testdata/n26.json".to_string()]).unwrap();
+        assert_eq!(registry.names(), vec!["gls", "dkb", "camt", "mt940", "n26"]);
+        let n26 = include_bytes!("../../testdata/n26.csv");
+        assert_eq!(registry.detect(n26, None).map(|x| x.name()), Ok("n26"));
+        assert!(registry.find("ing").is_err());
+
+        let twice = [
+            "testdata/n26.json".to_string(),
+            "testdata/n26.json".to_string(),
+        ];
+        assert!(Registry::with_mappings(&twice).is_err());
+    }
+
+    #[test]
+    fn test_short_row() {
+        let row = |fields: &[&str]| Row {
+            line: 3,
+            fields: fields.iter().map(|x| x.to_string()).collect(),
+        };
+        assert_eq!(
+            Skipped::short_row(&row(&["Saldo", "1,00"]), 6),
+            Some(Skipped {
+                line: 3,
+                reason: "2 fields, expected at least 6".to_string()
+            })
+        );
+        assert_eq!(Skipped::short_row(&row(&[""]), 6), None);
+    }
+
+    #[test]
+    fn test_header() {
+        let line = "\"b\";\"a;x\";c";
+        assert!(is_header(line, ';', Some('"'), &["a;x", "c"]));
+        assert!(!is_header(line, ';', Some('"'), &["a", "c"]));
+
+        let rows = csv::parse(&format!("account\n{line}"), ';', Some('"')).unwrap();
+        assert_eq!(find_header(&rows, &["a;x", "c"]), Ok((1, [1, 2])));
+        assert!(find_header(&rows, &["d"]).is_err());
+    }
+}
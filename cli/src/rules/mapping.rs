@@ -0,0 +1,367 @@
+use crate::rules::csv;
+use crate::rules::encoding::Encoding;
+use crate::rules::importer::{BankImporter, Import, Skipped, is_header};
+use crate::rules::{CsvRecord, is_bank_name, parse_date};
+use ausgabenzettel_core::Money;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::fs;
+
+/// A column given by its name within the header row or its position starting
+/// at 0.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+    Position(usize),
+    Name(String),
+}
+
+/// Columns read from an export, missing text columns are left empty.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Columns {
+    pub booking_date: Column,
+    #[serde(default)]
+    pub value_date: Option<Column>,
+    pub amount: Column,
+    #[serde(default)]
+    pub currency: Option<Column>,
+    #[serde(default)]
+    pub name: Option<Column>,
+    #[serde(default)]
+    pub iban: Option<Column>,
+    #[serde(default)]
+    pub reference: Option<Column>,
+    #[serde(default)]
+    pub booking_text: Option<Column>,
+    #[serde(default)]
+    pub creditor_id: Option<Column>,
+    #[serde(default)]
+    pub mandate_reference: Option<Column>,
+}
+
+/// Positions of the [`Columns`] within the rows.
+struct Positions {
+    booking_date: usize,
+    value_date: Option<usize>,
+    amount: usize,
+    currency: Option<usize>,
+    name: Option<usize>,
+    iban: Option<usize>,
+    reference: Option<usize>,
+    booking_text: Option<usize>,
+    creditor_id: Option<usize>,
+    mandate_reference: Option<usize>,
+}
+
+impl Positions {
+    /// Number of fields a row needs to contain all columns.
+    fn width(&self) -> usize {
+        [
+            Some(self.booking_date),
+            self.value_date,
+            Some(self.amount),
+            self.currency,
+            self.name,
+            self.iban,
+            self.reference,
+            self.booking_text,
+            self.creditor_id,
+            self.mandate_reference,
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or_default()
+            + 1
+    }
+}
+
+/// Describes the CSV export of a bank, so that it can be read without code.
+///
+/// ```json
+/// {
+///   "name": "n26",
+///   "delimiter": ",",
+///   "columns": {
+///     "booking_date": "Booking Date",
+///     "amount": "Amount (EUR)",
+///     "name": "Partner Name",
+///     "iban": "Partner Iban",
+///     "reference": "Payment Reference"
+///   },
+///   "date_format": "%Y-%m-%d",
+///   "decimal_separator": ".",
+///   "thousands_separator": null
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    /// Used as input prefix like the built-in banks.
+    pub name: String,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Encloses fields containing the delimiter, null if the export has none.
+    #[serde(default = "default_quote")]
+    pub quote: Option<char>,
+    /// Detected unless given.
+    #[serde(default)]
+    pub encoding: Option<Encoding>,
+    /// Lines before the header row, e.g. account details.
+    #[serde(default)]
+    pub skip: usize,
+    /// False if the export has no header row, columns are given by position then.
+    #[serde(default = "default_header")]
+    pub header: bool,
+    pub columns: Columns,
+    /// chrono format of the dates, e.g. `%Y-%m-%d`, defaults to German dates.
+    #[serde(default)]
+    pub date_format: Option<String>,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    #[serde(default = "default_thousands_separator")]
+    pub thousands_separator: Option<char>,
+    /// Currency of exports without currency column.
+    #[serde(default = "default_currency")]
+    pub currency: String,
+}
+
+fn default_delimiter() -> char {
+    ';'
+}
+
+fn default_quote() -> Option<char> {
+    Some('"')
+}
+
+fn default_header() -> bool {
+    true
+}
+
+fn default_decimal_separator() -> char {
+    ','
+}
+
+fn default_thousands_separator() -> Option<char> {
+    Some('.')
+}
+
+fn default_currency() -> String {
+    "EUR".to_string()
+}
+
+impl Mapping {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read mapping file: {}", e))?;
+        let mapping: Mapping = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse mapping: {}", e))?;
+        if !is_bank_name(&mapping.name) {
+            return Err(format!(
+                "Invalid bank name '{}', use at least two lowercase letters, digits, '-' or '_'",
+                mapping.name
+            ));
+        }
+        if !mapping.header && !mapping.names().is_empty() {
+            return Err("Columns have to be given by position without header row".to_string());
+        }
+        Ok(mapping)
+    }
+
+    fn columns(&self) -> [Option<&Column>; 10] {
+        let columns = &self.columns;
+        [
+            Some(&columns.booking_date),
+            columns.value_date.as_ref(),
+            Some(&columns.amount),
+            columns.currency.as_ref(),
+            columns.name.as_ref(),
+            columns.iban.as_ref(),
+            columns.reference.as_ref(),
+            columns.booking_text.as_ref(),
+            columns.creditor_id.as_ref(),
+            columns.mandate_reference.as_ref(),
+        ]
+    }
+
+    /// Column names expected within the header row.
+    fn names(&self) -> Vec<&str> {
+        self.columns()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| match x {
+                Column::Name(x) => Some(x.as_str()),
+                Column::Position(_) => None,
+            })
+            .collect()
+    }
+
+    fn positions(&self, header: &[String]) -> Result<Positions, String> {
+        let position = |column: &Column| match column {
+            Column::Position(x) => Ok(*x),
+            Column::Name(name) => header
+                .iter()
+                .position(|x| x.trim() == name)
+                .ok_or_else(|| format!("Missing column {}", name)),
+        };
+        let optional = |column: &Option<Column>| column.as_ref().map(position).transpose();
+        let columns = &self.columns;
+        Ok(Positions {
+            booking_date: position(&columns.booking_date)?,
+            value_date: optional(&columns.value_date)?,
+            amount: position(&columns.amount)?,
+            currency: optional(&columns.currency)?,
+            name: optional(&columns.name)?,
+            iban: optional(&columns.iban)?,
+            reference: optional(&columns.reference)?,
+            booking_text: optional(&columns.booking_text)?,
+            creditor_id: optional(&columns.creditor_id)?,
+            mandate_reference: optional(&columns.mandate_reference)?,
+        })
+    }
+
+    fn parse_date(&self, date: &str) -> Result<NaiveDate, String> {
+        match &self.date_format {
+            Some(format) => NaiveDate::parse_from_str(date.trim(), format)
+                .map_err(|e| format!("Invalid date '{}': {}", date.trim(), e)),
+            None => parse_date(date),
+        }
+    }
+}
+
+impl BankImporter for Mapping {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        let names = self.names();
+        self.header && !names.is_empty() && is_header(line, self.delimiter, self.quote, &names)
+    }
+
+    fn parse(&self, content: &str) -> Result<Import, String> {
+        // skipped lines may be anything, they are blanked to keep line numbers
+        let content = csv::normalize(content);
+        let rest = content.splitn(self.skip + 1, '\n').nth(self.skip);
+        let content = format!("{}{}", "\n".repeat(self.skip), rest.unwrap_or_default());
+        let mut rows = csv::parse(&content, self.delimiter, self.quote)?
+            .into_iter()
+            .skip(self.skip);
+        let positions = if self.header {
+            let header = rows.next().ok_or("Missing header row")?;
+            self.positions(&header.fields)?
+        } else {
+            self.positions(&[])?
+        };
+        let width = positions.width();
+
+        let mut import = Import::default();
+        for row in rows {
+            let fields = &row.fields;
+            if fields.len() < width {
+                import.skipped.extend(Skipped::short_row(&row, width));
+                continue;
+            }
+            let text = |x: Option<usize>| x.map_or("", |x| fields[x].trim()).to_string();
+
+            let currency = positions
+                .currency
+                .map(|x| fields[x].trim())
+                .filter(|x| !x.is_empty())
+                .unwrap_or(&self.currency);
+            let amount = Money::parse_with(
+                &fields[positions.amount],
+                currency,
+                self.decimal_separator,
+                self.thousands_separator,
+            )
+            .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            let booking_date = self
+                .parse_date(&fields[positions.booking_date])
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+            let value_date = positions
+                .value_date
+                .map(|x| fields[x].trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| self.parse_date(x))
+                .transpose()
+                .map_err(|e| format!("Failed to parse line {}: {}", row.line, e))?;
+
+            import.records.push(CsvRecord {
+                booking_date,
+                value_date,
+                amount,
+                reference: text(positions.reference),
+                iban: text(positions.iban),
+                name: text(positions.name),
+                booking_text: text(positions.booking_text),
+                creditor_id: text(positions.creditor_id),
+                mandate_reference: text(positions.mandate_reference),
+                refund_of: None,
+                transfer: false,
+                transfer_pair: None,
+            });
+        }
+
+        Ok(import)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture() {
+        let mapping = Mapping::load("testdata/n26.json").unwrap();
+        let records = mapping
+            .parse(include_str!("../../testdata/n26.csv"))
+            .unwrap()
+            .records;
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 4).unwrap()
+        );
+        assert_eq!(records[0].value_date, NaiveDate::from_ymd_opt(2026, 1, 5));
+        assert_eq!(records[0].name, "Bäckerei, Inh. Korn");
+        assert_eq!(records[0].iban, "");
+        assert_eq!(records[0].reference, "Brot \"groß\"");
+        assert_eq!(records[0].amount, Money::new(-1234, "EUR"));
+        assert_eq!(records[1].amount, Money::new(123450, "EUR"));
+        assert_eq!(records[1].iban, "DE27100777770209299700");
+    }
+
+    #[test]
+    fn test_parse_without_header() {
+        let mapping: Mapping = serde_json::from_str(
+            r#"{
+                "name": "spk",
+                "encoding": "windows-1252",
+                "skip": 1,
+                "header": false,
+                "columns": {"booking_date": 0, "amount": 2, "name": 1},
+                "date_format": "%d.%m.%y"
+            }"#,
+        )
+        .unwrap();
+        let content = mapping
+            .decode(b"Umsaetze\n02.01.26;M\xfcller;-1.000,00\n", None)
+            .unwrap();
+        let records = mapping.parse(&content).unwrap().records;
+
+        assert_eq!(records[0].name, "Müller");
+        assert_eq!(records[0].amount, Money::new(-100000, "EUR"));
+        assert!(!mapping.detect("Datum;Name;Betrag"));
+
+        let error = mapping.parse("Umsaetze\n2026-01-02;x;1,00").unwrap_err();
+        assert!(error.starts_with("Failed to parse line 2:"), "{}", error);
+    }
+}
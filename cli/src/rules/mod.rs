@@ -1,103 +1,475 @@
+use ausgabenzettel_core::{
+    Category, Expense, Kind, Money,
+    category::{self, ancestors},
+    document, fingerprint,
+};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
+use std::str::FromStr;
 
+pub mod camt;
+pub mod condition;
+pub mod csv;
 pub mod dkb;
+pub mod encoding;
 pub mod gls;
+pub mod importer;
+pub mod mapping;
+pub mod mt940;
+pub mod split;
 
-pub use dkb::read_csv as read_dkb_csv;
-pub use gls::read_csv as read_gls_csv;
+use condition::{Condition, FieldMatch};
+use encoding::Encoding;
+use importer::{Import, Registry, Skipped};
+use split::{Share, Split};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Category {
-    pub index: usize,
-    pub title: String,
-    pub description: String,
+/// Fields of a [`CsvRecord`] the lookup rules can match, in prompt order.
+pub const FIELDS: [&str; 6] = [
+    "REFERENCE",
+    "NAME",
+    "IBAN",
+    "BOOKING_TEXT",
+    "CREDITOR_ID",
+    "MANDATE_REFERENCE",
+];
+
+/// Whether the name can be used as bank prefix of an input, single letters
+/// are left to Windows drives.
+pub fn is_bank_name(name: &str) -> bool {
+    name.len() > 1
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// A bank export given as `bank:path`, e.g. `dkb:visa.csv`.
+///
+/// The bank of paths without a bank prefix is detected from the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    pub bank: Option<String>,
+    pub path: String,
 }
 
+impl FromStr for Input {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bank, path) = match s.split_once(':') {
+            Some((bank, path)) if is_bank_name(bank) => (Some(bank), path),
+            _ => (None, s),
+        };
+        if path.is_empty() {
+            return Err(format!("Missing path in input '{}'", s));
+        }
+        Ok(Input {
+            bank: bank.map(|x| x.to_string()),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Input {
+    /// Reads the export, an explicit bank has to match the detected one.
+    ///
+    /// The `encoding` is detected unless given, see
+    /// [`importer::BankImporter::decode`].
+    pub fn read(&self, registry: &Registry, encoding: Option<Encoding>) -> Result<Import, String> {
+        let import = fs::read(&self.path)
+            .map_err(|e| format!("Failed to read CSV file: {}", e))
+            .and_then(|bytes| {
+                let importer = match self.bank.as_deref() {
+                    Some(bank) => {
+                        let importer = registry.find(bank)?;
+                        if let Ok(detected) = registry.detect(&bytes, encoding)
+                            && detected.name() != bank
+                        {
+                            return Err(format!(
+                                "Expected {} export, found {}",
+                                bank,
+                                detected.name()
+                            ));
+                        }
+                        importer
+                    }
+                    None => registry.detect(&bytes, encoding)?,
+                };
+                importer.parse(&importer.decode(&bytes, encoding)?)
+            });
+        import.map_err(|e| format!("{}: {}", self.path, e))
+    }
+}
+
+/// Records of several inputs, see [`read_inputs`].
+#[derive(Debug, Default)]
+pub struct Records {
+    pub records: Vec<CsvRecord>,
+    /// Rows left out with the path of their input.
+    pub skipped: Vec<(String, Skipped)>,
+}
+
+/// Reads all inputs into one chronological record stream.
+///
+/// Transactions contained in several inputs, e.g. overlapping exports of the
+/// same account, are only kept once. Equal transactions within one input are
+/// all kept.
+///
+/// Skipped rows are returned, with `strict` the first one is an error
+/// instead.
+pub fn read_inputs(
+    inputs: &[Input],
+    registry: &Registry,
+    encoding: Option<Encoding>,
+    strict: bool,
+) -> Result<Records, String> {
+    let mut seen = HashSet::new();
+    let mut result = Records::default();
+    for input in inputs {
+        let import = input.read(registry, encoding)?;
+        if strict && let Some(row) = import.skipped.first() {
+            return Err(format!(
+                "{}: Unexpected line {}: {}",
+                input.path, row.line, row.reason
+            ));
+        }
+        result
+            .skipped
+            .extend(import.skipped.into_iter().map(|x| (input.path.clone(), x)));
+
+        let mut known = HashSet::new();
+        let mut occurrences = HashMap::new();
+        for record in import.records {
+            let occurrence = occurrences.entry(record.fingerprint(0)).or_insert(0);
+            let fingerprint = record.fingerprint(*occurrence);
+            *occurrence += 1;
+            if !seen.contains(&fingerprint) {
+                known.insert(fingerprint);
+                result.records.push(record);
+            }
+        }
+        seen.extend(known);
+    }
+    // stable, so records of the same day keep the order of their input
+    result.records.sort_by_key(|x| x.booking_date);
+    Ok(result)
+}
+
+/// Rule assigning the category to matching records, see [`Condition`] for
+/// the JSON of the conditions.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CategoryLookupEntry {
-    pub field: String,
-    pub value: String,
-    pub category: usize,
-    pub match_type: MatchType,
+    #[serde(flatten)]
+    pub condition: Condition,
+    pub category: String,
+    /// Rules of higher priority win, those of equal priority in file order.
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub priority: i32,
+    /// Parts of matching transactions going to other categories, the
+    /// category of the rule gets the rest.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split: Vec<Split>,
+}
+
+fn is_default_priority(priority: &i32) -> bool {
+    *priority == 0
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CsvRecord {
-    pub date: Option<String>,
-    pub amount: String,
+    pub booking_date: NaiveDate,
+    /// Day the amount was credited or debited, if the bank provides it.
+    pub value_date: Option<NaiveDate>,
+    pub amount: Money,
     pub reference: String,
     pub iban: String,
     pub name: String,
+    /// Kind of booking as named by the bank, e.g. `Lastschrift`.
+    #[serde(default)]
+    pub booking_text: String,
+    /// SEPA creditor identifier of direct debits, empty if unknown.
+    #[serde(default)]
+    pub creditor_id: String,
+    /// Reference of the SEPA direct debit mandate, empty if unknown.
+    #[serde(default)]
+    pub mandate_reference: String,
+    /// Index of the expense within the same export this income refunds.
+    #[serde(default, skip)]
+    pub refund_of: Option<usize>,
+    /// Set when the counterparty is an own account.
+    #[serde(default, skip)]
+    pub transfer: bool,
+    /// Index of the transaction on the other own account.
+    #[serde(default, skip)]
+    pub transfer_pair: Option<usize>,
 }
 
 impl CsvRecord {
-    pub fn non_empty_fields(&self) -> &[&str] {
-        match (
-            self.reference.is_empty(),
-            self.name.is_empty(),
-            self.iban.is_empty(),
-        ) {
-            (true, true, true) => &[],
-            (true, true, false) => &["IBAN"],
-            (true, false, true) => &["NAME"],
-            (false, true, true) => &["REFERENCE"],
-            (true, false, false) => &["NAME", "IBAN"],
-            (false, true, false) => &["REFERENCE", "IBAN"],
-            (false, false, true) => &["REFERENCE", "NAME"],
-            (false, false, false) => &["REFERENCE", "NAME", "IBAN"],
+    /// Value of a field of the lookup rules like `NAME`, case insensitive.
+    pub fn field(&self, field: &str) -> Option<&str> {
+        match field.to_uppercase().as_str() {
+            "REFERENCE" => Some(&self.reference),
+            "NAME" => Some(&self.name),
+            "IBAN" => Some(&self.iban),
+            "BOOKING_TEXT" => Some(&self.booking_text),
+            "CREDITOR_ID" => Some(&self.creditor_id),
+            "MANDATE_REFERENCE" => Some(&self.mandate_reference),
+            _ => None,
         }
     }
 
+    pub fn non_empty_fields(&self) -> Vec<&'static str> {
+        FIELDS
+            .into_iter()
+            .filter(|x| self.field(x).is_some_and(|x| !x.is_empty()))
+            .collect()
+    }
+
     pub fn get_title(&self) -> String {
         format!("{} - {}", self.name, self.reference)
     }
+
+    /// Transfer between own accounts, otherwise income for positive amounts
+    /// and expense for negative ones.
+    pub fn kind(&self) -> Kind {
+        if self.transfer {
+            Kind::Transfer
+        } else {
+            Kind::of(&self.amount)
+        }
+    }
+
+    /// Fingerprint of the transaction, `occurrence` counts preceding equal ones.
+    pub fn fingerprint(&self, occurrence: usize) -> String {
+        fingerprint(
+            self.booking_date,
+            &self.amount,
+            &self.iban,
+            &self.reference,
+            occurrence,
+        )
+    }
 }
 
-impl CategoryLookupEntry {
-    pub fn matches(&self, record: &CsvRecord) -> bool {
-        let field_upper = self.field.to_uppercase();
+#[cfg(test)]
+impl CsvRecord {
+    /// An expense or income booked on 2026-02-01 with the amount as written in
+    /// German exports, further fields can be set with struct update syntax.
+    pub fn test(name: &str, amount: &str) -> Self {
+        CsvRecord {
+            booking_date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+            value_date: None,
+            amount: Money::parse_german(amount, "EUR").unwrap(),
+            reference: String::new(),
+            iban: String::new(),
+            name: name.to_string(),
+            booking_text: String::new(),
+            creditor_id: String::new(),
+            mandate_reference: String::new(),
+            refund_of: None,
+            transfer: false,
+            transfer_pair: None,
+        }
+    }
+}
 
-        let expect_value = match field_upper.as_str() {
-            "IBAN" => &record.iban,
-            "REFERENCE" => &record.reference,
-            "NAME" => &record.name,
-            _ => panic!("Unknown field: {}", self.field),
-        };
-        match self.match_type {
-            MatchType::Exact => &self.value == expect_value,
-            MatchType::Contains => expect_value
-                .to_uppercase()
-                .contains(&self.value.to_uppercase()),
+impl CategoryLookupEntry {
+    /// Rule matching a single field.
+    pub fn new(field: &str, value: &str, category: &str, match_type: MatchType) -> Self {
+        CategoryLookupEntry {
+            condition: Condition::Field(FieldMatch::new(field, value, match_type)),
+            category: category.to_string(),
+            priority: 0,
+            split: Vec::new(),
         }
     }
+
+    pub fn matches(&self, record: &CsvRecord) -> bool {
+        self.condition.matches(record)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum MatchType {
     Exact,
     Contains,
+    /// The value is a regular expression found within the field.
+    Regex,
+}
+
+/// Removes the whitespace of an IBAN as printed, e.g. `DE11 2233`.
+pub fn normalize_iban(iban: &str) -> String {
+    iban.split_whitespace().collect()
 }
 
 pub fn load_categories(path: &str) -> Result<Vec<Category>, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read categories file: {}", e))?;
-    let categories: Vec<Category> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse categories JSON: {}", e))?;
-    Ok(categories)
+    category::parse(&content).map_err(|e| format!("Failed to parse categories: {}", e))
+}
+
+/// Returns the indices and categories of the matching lookup rules, the
+/// deciding one first: highest priority, then first in the lookup.
+///
+/// Rules for categories of another kind are ignored, so that e.g. a refund
+/// from a shop does not end up within the expenses at that shop.
+pub fn matching_rules<'a>(
+    record: &CsvRecord,
+    lookup: &[CategoryLookupEntry],
+    categories: &'a [Category],
+) -> Vec<(usize, &'a Category)> {
+    let kind = record.kind();
+    let mut rules: Vec<(usize, &Category)> = lookup
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.matches(record))
+        .filter_map(|(i, rule)| {
+            categories
+                .iter()
+                .find(|x| x.id == rule.category && x.kind == kind)
+                .map(|x| (i, x))
+        })
+        .collect();
+    // stable, so rules of equal priority keep their order
+    rules.sort_by_key(|(i, _)| std::cmp::Reverse(lookup[*i].priority));
+    rules
+}
+
+/// Returns the category of the deciding lookup rule, see [`matching_rules`].
+pub fn categorize<'a>(
+    record: &CsvRecord,
+    lookup: &[CategoryLookupEntry],
+    categories: &'a [Category],
+) -> Option<&'a Category> {
+    matching_rules(record, lookup, categories)
+        .first()
+        .map(|(_, x)| *x)
+}
+
+/// Splits the amount of the record by the deciding lookup rule, see
+/// [`split::split_amount`], the part of the rule's category first.
+///
+/// `amount` is distributed in proportion to the parts of the record's amount,
+/// so that a partial refund is split like the refunded expense, see
+/// [`split::scale`]. Parts without amount are left out. Splits to unknown categories or ones of
+/// another kind leave their part without category.
+pub fn categorize_parts<'a>(
+    record: &CsvRecord,
+    amount: &Money,
+    lookup: &[CategoryLookupEntry],
+    categories: &'a [Category],
+) -> Vec<(Option<&'a Category>, Money)> {
+    let Some(&(rule, category)) = matching_rules(record, lookup, categories).first() else {
+        return vec![(None, amount.clone())];
+    };
+    let split = &lookup[rule].split;
+    let parts: Vec<(Option<&Category>, Money)> = std::iter::once(Some(category))
+        .chain(split.iter().map(|x| {
+            categories
+                .iter()
+                .find(|c| c.id == x.category && c.kind == category.kind)
+        }))
+        .zip(split::scale(
+            &split::split_amount(&record.amount, split),
+            amount,
+        ))
+        .filter(|(_, x)| x.minor != 0)
+        .collect();
+    if parts.is_empty() {
+        vec![(Some(category), amount.clone())]
+    } else {
+        parts
+    }
+}
+
+/// Amounts per currency code.
+pub type Totals = BTreeMap<String, Money>;
+
+fn add_to(totals: &mut Totals, amount: &Money) {
+    *totals
+        .entry(amount.currency.clone())
+        .or_insert_with(|| Money::zero(&amount.currency)) += amount.clone();
+}
+
+/// Sums the amounts per category and currency, including the amounts of all
+/// subcategories.
+///
+/// The returned map contains `None` for records without a category, per kind
+/// of record. Refunds reduce the expenses of the category of the refunded
+/// expense, transfers are left out. Split transactions count towards the
+/// category of each part.
+pub fn category_totals<'a>(
+    records: &[CsvRecord],
+    lookup: &[CategoryLookupEntry],
+    categories: &'a [Category],
+) -> HashMap<(Kind, Option<&'a str>), Totals> {
+    let mut totals: HashMap<(Kind, Option<&str>), Totals> = HashMap::new();
+    for record in records.iter().filter(|x| x.kind() != Kind::Transfer) {
+        let (kind, amount, categorized) = match record.refund_of {
+            Some(original) => (Kind::Expense, -record.amount.abs(), &records[original]),
+            None => (record.kind(), record.amount.abs(), record),
+        };
+        for (category, amount) in categorize_parts(categorized, &amount, lookup, categories) {
+            let keys = match category {
+                Some(category) => ancestors(categories, category)
+                    .into_iter()
+                    .map(|x| (kind, Some(&x.id as &str)))
+                    .collect(),
+                None => vec![(kind, None)],
+            };
+            for key in keys {
+                add_to(totals.entry(key).or_default(), &amount);
+            }
+        }
+    }
+    totals
+}
+
+/// Income minus expenses per currency, without transfers.
+pub fn net_cash_flow(records: &[CsvRecord]) -> Totals {
+    let mut totals = Totals::new();
+    for record in records.iter().filter(|x| x.kind() != Kind::Transfer) {
+        add_to(&mut totals, &record.amount);
+    }
+    totals
+}
+
+/// Formats the totals like `12.00€, 3.50USD`.
+pub fn format_totals(totals: &Totals) -> String {
+    totals
+        .values()
+        .map(|amount| amount.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub fn load_lookup(path: &str) -> Result<Vec<CategoryLookupEntry>, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read lookup file: {}", e))?;
-    let entries: Vec<CategoryLookupEntry> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse lookup JSON: {}", e))?;
+    let entries: Vec<CategoryLookupEntry> = serde_json::from_str(&content).map_err(|e| {
+        format!(
+            "Failed to parse lookup JSON: {} (lookup files using category indices need `auseinnahmen migrate`)",
+            e
+        )
+    })?;
+    for (index, entry) in entries.iter().enumerate() {
+        entry
+            .condition
+            .validate()
+            .and_then(|_| split::validate(&entry.split))
+            .map_err(|e| format!("Rule {} of the lookup: {}", index, e))?;
+    }
     Ok(entries)
 }
 
-pub fn find_category(record: &CsvRecord, lookup: &[CategoryLookupEntry]) -> bool {
-    lookup.iter().any(|x| x.matches(record))
+pub fn find_category(
+    record: &CsvRecord,
+    lookup: &[CategoryLookupEntry],
+    categories: &[Category],
+) -> bool {
+    categorize(record, lookup, categories).is_some()
 }
 
 pub fn prompt<'a>(field: &'a str, elements: &'a [&'a str]) -> Option<usize> {
@@ -131,27 +503,24 @@ pub fn prompt<'a>(field: &'a str, elements: &'a [&'a str]) -> Option<usize> {
 }
 
 pub fn prompt_field(record: &CsvRecord) -> Option<(&str, &str)> {
-    let fields: &[&str] = record.non_empty_fields();
-    let field = prompt("field", fields).map(|x| fields[x]);
-    field
-        .iter()
-        .filter_map(|x| match *x {
-            "IBAN" => Some((*x, &record.iban as &str)),
-            "NAME" => Some((*x, &record.name as &str)),
-            "REFERENCE" => Some((*x, &record.reference as &str)),
-            _ => None,
-        })
-        .next()
+    let fields = record.non_empty_fields();
+    let field = prompt("field", &fields).map(|x| fields[x])?;
+    Some((field, record.field(field)?))
 }
 
-pub fn prompt_category(categories: &[Category]) -> Option<usize> {
-    let prompt_title: Vec<&str> = categories.iter().map(|x| &x.title as &str).collect();
-    prompt("category", &prompt_title)
+pub fn prompt_category(categories: &[Category]) -> Option<&Category> {
+    let tree = category::tree(categories);
+    let titles: Vec<String> = tree
+        .iter()
+        .map(|(depth, x)| format!("{}{}", "  ".repeat(*depth), x.title))
+        .collect();
+    let prompt_title: Vec<&str> = titles.iter().map(|x| x as &str).collect();
+    prompt("category", &prompt_title).map(|x| tree[x].1)
 }
 
 pub fn prompt_search_value(field: &str, default: &str) -> String {
     eprint!(
-        "Enter search value for {} (default: '{}'): ",
+        "Enter search value for {} (default: '{}', /regex/ for a pattern): ",
         field, default
     );
     io::stdout().flush().unwrap();
@@ -180,52 +549,199 @@ pub fn prompt_for_category(
     eprintln!("NAME     : {}", record.name);
     eprintln!("IBAN     : {}", record.iban);
     eprintln!("REFERENCE: {}", record.reference);
+    for (label, value) in [
+        ("BOOKING  ", &record.booking_text),
+        ("CREDITOR ", &record.creditor_id),
+        ("MANDATE  ", &record.mandate_reference),
+    ] {
+        if !value.is_empty() {
+            eprintln!("{}: {}", label, value);
+        }
+    }
     eprintln!();
 
-    let category_index = prompt_category(categories)?;
+    let kind = record.kind();
+    let candidates: Vec<Category> = categories
+        .iter()
+        .filter(|x| x.kind == kind)
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        eprintln!("No {:?} categories defined, skipping.", kind);
+        return None;
+    }
+    let category = prompt_category(&candidates)?;
 
     eprintln!();
     eprintln!();
 
     let (field, default_value) = prompt_field(record)?;
 
-    let value = prompt_search_value(field, default_value);
-
-    let match_type = if value == default_value {
-        MatchType::Exact
-    } else {
-        MatchType::Contains
+    let (value, match_type) = loop {
+        let value = prompt_search_value(field, default_value);
+        match search_match_type(&value, default_value) {
+            Ok(x) => break x,
+            Err(e) => eprintln!("{}", e),
+        }
     };
 
-    let rule = CategoryLookupEntry {
-        field: field.to_string(),
-        value,
-        category: category_index,
-        match_type,
-    };
+    let mut entry = CategoryLookupEntry::new(field, &value, &category.id, match_type);
+    entry.split = prompt_splits(&candidates, category, &record.amount.abs());
+    Some(entry)
+}
+
+/// Asks for parts of the transaction going to other categories than the
+/// chosen one, e.g. the household goods on a supermarket receipt.
+pub fn prompt_splits(categories: &[Category], category: &Category, amount: &Money) -> Vec<Split> {
+    let mut splits = Vec::new();
+    loop {
+        eprint!(
+            "Split off an amount for another category (e.g. '5,00' or '20%', empty for none): "
+        );
+        io::stdout().flush().unwrap();
 
-    Some(rule)
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).unwrap() == 0 {
+            return splits;
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return splits;
+        }
+
+        let share = match input.parse::<Share>() {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let Some(other) = prompt_category(categories) else {
+            continue;
+        };
+        splits.push(Split {
+            category: other.id.clone(),
+            share,
+        });
+        if let Err(e) = split::validate(&splits) {
+            eprintln!("{}", e);
+            splits.pop();
+            continue;
+        }
+
+        let titles = std::iter::once(&category.title).chain(splits.iter().map(|x| {
+            categories
+                .iter()
+                .find(|c| c.id == x.category)
+                .map_or(&x.category, |c| &c.title)
+        }));
+        for (title, part) in titles.zip(split::split_amount(amount, &splits)) {
+            eprintln!("{}: {}", title, part);
+        }
+    }
 }
 
-pub fn date_to_timestamp(date_str: &str, counter: u32) -> Option<u64> {
-    let parts: Vec<&str> = date_str.trim().split('.').collect();
-    if parts.len() != 3 {
-        return None;
+/// Match type of an entered search value: `Exact` for the default, `Regex`
+/// for values like `/AMZN Mktp DE\*\w+/`, otherwise `Contains`.
+pub fn search_match_type(value: &str, default: &str) -> Result<(String, MatchType), String> {
+    if value == default {
+        return Ok((value.to_string(), MatchType::Exact));
+    }
+    match value.strip_prefix('/').and_then(|x| x.strip_suffix('/')) {
+        Some(pattern) if !pattern.is_empty() => {
+            FieldMatch::new("", pattern, MatchType::Regex).regex()?;
+            Ok((pattern.to_string(), MatchType::Regex))
+        }
+        _ => Ok((value.to_string(), MatchType::Contains)),
     }
-    let day = parts[0].parse::<u32>().ok()?;
-    let month = parts[1].parse::<u32>().ok()?;
-    let mut year = parts[2].parse::<i32>().ok()?;
-    if year < 2000 {
-        year += 2000
+}
+
+/// Parses dates of bank exports like `02.01.2026` or `02.01.26`.
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    let date = date.trim();
+    let (_, year) = date.rsplit_once('.').unwrap_or_default();
+    let format = if year.len() == 2 {
+        "%d.%m.%y"
+    } else {
+        "%d.%m.%Y"
     };
-    let hour = counter % 24;
-    let minute = (counter / 24) % 60;
-    let second = (counter / (24 * 60)) % 60;
+    NaiveDate::parse_from_str(date, format).map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
 
-    let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
-    let datetime = date.and_hms_opt(hour, minute, second)?;
-    let timestamp = datetime.and_utc().timestamp_millis();
-    Some(timestamp as u64)
+/// Parses an optional date, empty fields are None.
+pub fn parse_optional_date(date: &str) -> Result<Option<NaiveDate>, String> {
+    if date.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_date(date).map(Some)
+    }
+}
+
+/// Creates the document entries of the records, categorized by the lookup rules.
+///
+/// Refunds get the category of the refunded expense and reference its id,
+/// transfers keep their sign and reference the entry of the other account.
+/// Records split by their rule become one entry per part, see
+/// [`categorize_parts`]. The first part keeps the id and fingerprint of the
+/// record, the others follow on the next ids, append `-1`, `-2`, ... to the
+/// fingerprint and reference the first part.
+pub fn to_expenses(
+    records: &[CsvRecord],
+    lookup: &[CategoryLookupEntry],
+    categories: &[Category],
+) -> Vec<Expense> {
+    let parts: Vec<Vec<(Option<&Category>, Money)>> = records
+        .iter()
+        .map(|record| {
+            let categorized = record.refund_of.map_or(record, |x| &records[x]);
+            let amount = match record.kind() {
+                Kind::Transfer => record.amount.clone(),
+                _ => record.amount.abs(),
+            };
+            categorize_parts(categorized, &amount, lookup, categories)
+        })
+        .collect();
+    let mut per_day = HashMap::new();
+    let ids: Vec<Vec<String>> = records
+        .iter()
+        .zip(&parts)
+        .map(|(record, parts)| {
+            let offset = per_day.entry(record.booking_date).or_insert(0u32);
+            let ids = (0..parts.len() as u32)
+                .map(|x| Expense::id_on(record.booking_date, *offset + x))
+                .collect();
+            *offset += parts.len() as u32;
+            ids
+        })
+        .collect();
+    let mut occurrences = HashMap::new();
+
+    let mut expenses = Vec::new();
+    for ((record, parts), part_ids) in records.iter().zip(parts).zip(&ids) {
+        let occurrence = occurrences.entry(record.fingerprint(0)).or_insert(0);
+        let fingerprint = record.fingerprint(*occurrence);
+        *occurrence += 1;
+
+        for (n, ((category, amount), id)) in parts.into_iter().zip(part_ids).enumerate() {
+            expenses.push(Expense {
+                id: id.clone(),
+                kind: record.kind(),
+                booking_date: Some(record.booking_date),
+                value_date: record.value_date,
+                category: category.map(|x| x.id.clone()),
+                title: record.get_title(),
+                amount,
+                fingerprint: Some(match n {
+                    0 => fingerprint.clone(),
+                    n => format!("{}-{}", fingerprint, n),
+                }),
+                refund_of: record.refund_of.map(|x| ids[x][0].clone()),
+                transfer_pair: record.transfer_pair.map(|x| ids[x][0].clone()),
+                split_of: (n > 0).then(|| part_ids[0].clone()),
+            });
+        }
+    }
+    expenses
 }
 
 pub fn generate_html(
@@ -233,43 +749,480 @@ pub fn generate_html(
     lookup: &[CategoryLookupEntry],
     categories: &[Category],
 ) -> String {
-    let mut html = String::from("<div id=\"details\">");
-    let mut date_counter = std::collections::HashMap::new();
-
-    for record in records {
-        let class = lookup.iter().find_map(|entry| {
-            if entry.matches(record) {
-                Some(entry.category)
-            } else {
-                None
-            }
-        });
-        let class = class.and_then(|x| categories.iter().find(|y| y.index == x));
-
-        let title = record.get_title();
-        let amount = record.amount.replace("-", "").replace(',', ".");
-        let amount = format!("{:.2}€", amount.parse::<f64>().unwrap_or(0.0));
-
-        // TODO: that should actually not be an option
-        let date_str = record.date.as_deref().unwrap_or("01.01.1111");
-        let counter = date_counter.entry(date_str.to_string()).or_insert(0u32);
-        let timestamp = date_to_timestamp(date_str, *counter).unwrap_or(0);
-        *counter += 1;
-        html.push_str(&format!(
-            "<details class=\"cat{}\" id=\"{timestamp}\">",
-            class.map(|x| x.index + 1).unwrap_or(404)
+    document::render(&to_expenses(records, lookup, categories), categories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: &str, parent: Option<&str>) -> Category {
+        Category {
+            parent: parent.map(|x| x.to_string()),
+            ..Category::new(id, id)
+        }
+    }
+
+    fn record(name: &str, amount: &str) -> CsvRecord {
+        CsvRecord::test(name, amount)
+    }
+
+    fn rule(name: &str, category: &str) -> CategoryLookupEntry {
+        CategoryLookupEntry::new("NAME", name, category, MatchType::Exact)
+    }
+
+    fn categories() -> Vec<Category> {
+        vec![
+            category("alltag", None),
+            category("ausgehen", None),
+            category("drogerie", Some("alltag")),
+        ]
+    }
+
+    #[test]
+    fn test_category_totals_roll_up() {
+        let categories = categories();
+        let lookup = vec![rule("REWE", "alltag"), rule("DM", "drogerie")];
+        let records = vec![
+            record("REWE", "-10,50"),
+            record("DM", "-4,50"),
+            record("Kiosk", "-1,00"),
+        ];
+        let totals = category_totals(&records, &lookup, &categories);
+        let eur = |x: Option<&str>| {
+            totals
+                .get(&(Kind::Expense, x))
+                .and_then(|x| x.get("EUR"))
+                .map(|x| x.minor)
+        };
+        assert_eq!(eur(Some("alltag")), Some(1500));
+        assert_eq!(eur(Some("drogerie")), Some(450));
+        assert_eq!(eur(Some("ausgehen")), None);
+        assert_eq!(eur(None), Some(100));
+    }
+
+    #[test]
+    fn test_match_creditor_id() {
+        let mut netflix = record("NETFLIX.COM", "-12,99");
+        netflix.creditor_id = "GB33ZZZSDDBARC0000007495895".to_string();
+        netflix.booking_text = "Lastschrift".to_string();
+        let rule = CategoryLookupEntry::new(
+            "creditor_id",
+            "gb33zzzsddbarc0000007495895",
+            "ausgehen",
+            MatchType::Contains,
+        );
+
+        assert!(rule.matches(&netflix));
+        assert!(!rule.matches(&record("Netflix", "-12,99")));
+        assert_eq!(
+            netflix.non_empty_fields(),
+            vec!["NAME", "BOOKING_TEXT", "CREDITOR_ID"]
+        );
+    }
+
+    #[test]
+    fn test_match_regex() {
+        let rule =
+            CategoryLookupEntry::new("NAME", r"^AMZN Mktp DE\*\w+$", "alltag", MatchType::Regex);
+        assert!(rule.matches(&record("AMZN Mktp DE*1A2B3C4", "-9,99")));
+        assert!(!rule.matches(&record("AMZN Mktp DE", "-9,99")));
+
+        assert_eq!(
+            search_match_type("/DE\\*\\w+/", "AMZN Mktp DE*1A2B3C4").map(|x| x.0),
+            Ok(r"DE\*\w+".to_string())
+        );
+        assert!(search_match_type("/DE*(/", "AMZN Mktp DE*1A2B3C4").is_err());
+        assert!(matches!(
+            search_match_type("/", "AMZN"),
+            Ok((_, MatchType::Contains))
         ));
-        html.push_str(&format!(
-            "<summary><span>{title}</span><span>{amount}</span></summary>"
+    }
+
+    #[test]
+    fn test_lookup_entry_json_is_backwards_compatible() {
+        let json = r#"{"field":"NAME","value":"REWE","match_type":"Exact","category":"alltag"}"#;
+        let entry: CategoryLookupEntry = serde_json::from_str(json).unwrap();
+        assert!(entry.matches(&record("REWE", "-1,00")));
+        assert_eq!(serde_json::to_string(&entry).unwrap(), json);
+
+        let entry: CategoryLookupEntry = serde_json::from_str(
+            r#"{"category": "alltag", "any": [{"field": "NAME", "value": "DM", "match_type": "Exact"}]}"#,
+        )
+        .unwrap();
+        assert!(entry.matches(&record("DM", "-1,00")));
+    }
+
+    #[test]
+    fn test_load_lookup_reports_invalid_regex() {
+        let path =
+            std::env::temp_dir().join(format!("auseinnahmen-lookup-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"[
+                {"field": "NAME", "value": "REWE", "category": "alltag", "match_type": "Exact"},
+                {"field": "NAME", "value": "DE*(", "category": "alltag", "match_type": "Regex"}
+            ]"#,
+        )
+        .unwrap();
+        let error = load_lookup(&path.display().to_string()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(
+            error.starts_with("Rule 1 of the lookup: Invalid regex 'DE*('"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_load_lookup_rejects_mixed_conditions() {
+        let path = std::env::temp_dir().join(format!(
+            "auseinnahmen-lookup-mixed-{}.json",
+            std::process::id()
         ));
-        html.push_str(
-        &format!("<div><div class=\"row\"><span>Category</span><span class=\"right-align\">{}</span></div>", 
-            class.map(|x|&x.title as &str).unwrap_or("Unknown"))
+        fs::write(
+            &path,
+            r#"[{"field": "NAME", "match": "REWE", "amount": {"lt": 50}, "category": "alltag"}]"#,
+        )
+        .unwrap();
+        let error = load_lookup(&path.display().to_string()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("Unknown condition key 'match'"), "{}", error);
+    }
+
+    #[test]
+    fn test_priority_decides() {
+        let categories = categories();
+        let mut lookup = vec![rule("REWE", "alltag"), rule("REWE", "ausgehen")];
+        let rewe = record("REWE", "-1,00");
+        assert_eq!(
+            categorize(&rewe, &lookup, &categories).unwrap().id,
+            "alltag"
+        );
+
+        lookup[1].priority = 1;
+        assert_eq!(
+            categorize(&rewe, &lookup, &categories).unwrap().id,
+            "ausgehen"
         );
-        html.push_str("<a href=\"#\">remove</a></div>");
-        html.push_str("</details>");
+        let ids: Vec<usize> = matching_rules(&rewe, &lookup, &categories)
+            .iter()
+            .map(|x| x.0)
+            .collect();
+        assert_eq!(ids, vec![1, 0]);
+        assert!(
+            serde_json::to_string(&lookup[1])
+                .unwrap()
+                .ends_with(r#""priority":1}"#)
+        );
+    }
+
+    #[test]
+    fn test_category_totals_per_currency() {
+        let categories = categories();
+        let lookup = vec![rule("REWE", "alltag")];
+        let mut foreign = record("REWE", "-2,00");
+        foreign.amount.currency = "USD".to_string();
+        let records = vec![record("REWE", "-10,50"), foreign];
+        let totals = category_totals(&records, &lookup, &categories);
+        assert_eq!(
+            format_totals(&totals[&(Kind::Expense, Some("alltag"))]),
+            "10.50€, 2.00USD"
+        );
+    }
+
+    #[test]
+    fn test_income_uses_income_categories() {
+        let mut categories = categories();
+        categories.push(Category {
+            kind: Kind::Income,
+            ..category("gehalt", None)
+        });
+        let lookup = vec![rule("ACME", "alltag"), rule("ACME", "gehalt")];
+        let records = vec![
+            record("ACME", "-10,00"),
+            record("ACME", "2500,00"),
+            record("Oma", "50,00"),
+        ];
+
+        let totals = category_totals(&records, &lookup, &categories);
+        assert_eq!(
+            format_totals(&totals[&(Kind::Expense, Some("alltag"))]),
+            "10.00€"
+        );
+        assert_eq!(
+            format_totals(&totals[&(Kind::Income, Some("gehalt"))]),
+            "2500.00€"
+        );
+        assert_eq!(format_totals(&totals[&(Kind::Income, None)]), "50.00€");
+        assert_eq!(format_totals(&net_cash_flow(&records)), "2540.00€");
+
+        let expenses = to_expenses(&records, &lookup, &categories);
+        assert_eq!(expenses[1].kind, Kind::Income);
+        assert_eq!(expenses[1].category.as_deref(), Some("gehalt"));
+        assert_eq!(expenses[1].amount, Money::new(250000, "EUR"));
+        let parsed = document::parse(&document::render(&expenses, &categories)).expenses;
+        assert_eq!(parsed[0].kind, Kind::Expense);
+        assert_eq!(parsed[2].kind, Kind::Income);
+    }
+
+    #[test]
+    fn test_refunds_reduce_expenses() {
+        let categories = categories();
+        let lookup = vec![rule("Shop", "drogerie")];
+        let mut records = vec![record("Shop", "-30,00"), record("Shop", "10,00")];
+        records[1].refund_of = Some(0);
+
+        let totals = category_totals(&records, &lookup, &categories);
+        assert_eq!(
+            format_totals(&totals[&(Kind::Expense, Some("alltag"))]),
+            "20.00€"
+        );
+        assert!(!totals.contains_key(&(Kind::Income, None)));
+
+        let expenses = to_expenses(&records, &lookup, &categories);
+        assert!(expenses[1].is_refund());
+        assert_eq!(expenses[1].category.as_deref(), Some("drogerie"));
+        assert_eq!(expenses[1].refund_of.as_ref(), Some(&expenses[0].id));
+    }
+
+    #[test]
+    fn test_split_transactions() {
+        let categories = categories();
+        let mut lookup = vec![rule("REWE", "alltag")];
+        lookup[0].split = vec![
+            Split {
+                category: "drogerie".to_string(),
+                share: Share::Amount(condition::Amount(500)),
+            },
+            Split {
+                category: "ausgehen".to_string(),
+                share: Share::Percent(10),
+            },
+        ];
+        let mut records = vec![
+            record("REWE", "-30,00"),
+            record("Kiosk", "-1,00"),
+            record("REWE", "6,00"),
+        ];
+        records[2].refund_of = Some(0);
+
+        let totals = category_totals(&records, &lookup, &categories);
+        let eur = |x: &str| format_totals(&totals[&(Kind::Expense, Some(x))]);
+        // the refund is split in proportion to the refunded expense
+        assert_eq!(eur("alltag"), "21.60€");
+        assert_eq!(eur("drogerie"), "4.00€");
+        assert_eq!(eur("ausgehen"), "2.40€");
+
+        let expenses = to_expenses(&records, &lookup, &categories);
+        let parts: Vec<(Option<&str>, i64)> = expenses
+            .iter()
+            .map(|x| (x.category.as_deref(), x.amount.minor))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                (Some("alltag"), 2200),
+                (Some("drogerie"), 500),
+                (Some("ausgehen"), 300),
+                (None, 100),
+                (Some("alltag"), 440),
+                (Some("drogerie"), 100),
+                (Some("ausgehen"), 60),
+            ]
+        );
+        let ids: HashSet<&str> = expenses.iter().map(|x| &x.id as &str).collect();
+        assert_eq!(ids.len(), expenses.len());
+        assert_eq!(expenses[1].split_of.as_ref(), Some(&expenses[0].id));
+        assert_eq!(expenses[2].split_of.as_ref(), Some(&expenses[0].id));
+        assert_eq!(expenses[3].split_of, None);
+        let fingerprint = expenses[0].fingerprint.clone().unwrap();
+        assert_eq!(expenses[2].fingerprint, Some(format!("{}-2", fingerprint)));
+        assert!(expenses[4..].iter().all(|x| x.is_refund()));
+        assert_eq!(expenses[5].refund_of.as_ref(), Some(&expenses[0].id));
+        assert_eq!(expenses[6].split_of.as_ref(), Some(&expenses[4].id));
+
+        let html = generate_html(&records, &lookup, &categories);
+        assert_eq!(document::parse(&html).expenses, expenses);
+        assert_eq!(document::merge(&html, &expenses, &categories).added, 0);
+    }
+
+    #[test]
+    fn test_transfers_are_not_totaled() {
+        let categories = categories();
+        let lookup = vec![rule("Ich", "alltag")];
+        let mut records = vec![
+            record("Ich", "-100,00"),
+            record("Ich", "100,00"),
+            record("REWE", "-5,00"),
+        ];
+        for (i, record) in records.iter_mut().take(2).enumerate() {
+            record.transfer = true;
+            record.transfer_pair = Some(1 - i);
+        }
+
+        let totals = category_totals(&records, &lookup, &categories);
+        assert_eq!(totals.len(), 1);
+        assert_eq!(format_totals(&net_cash_flow(&records)), "-5.00€");
+
+        let expenses = to_expenses(&records, &lookup, &categories);
+        assert_eq!(expenses[0].kind, Kind::Transfer);
+        assert_eq!(expenses[0].category, None);
+        assert_eq!(expenses[0].amount, Money::new(-10000, "EUR"));
+        assert_eq!(expenses[0].transfer_pair.as_ref(), Some(&expenses[1].id));
+    }
+
+    #[test]
+    fn test_generate_html_is_parsed_by_backend_format() {
+        let categories = categories();
+        let lookup = vec![rule("DM", "drogerie")];
+        let records = vec![record("DM", "-4,50"), record("Kiosk", "-1,00")];
+        let expenses = document::parse(&generate_html(&records, &lookup, &categories)).expenses;
+        assert_eq!(expenses.len(), 2);
+        assert_eq!(expenses[0].category.as_deref(), Some("drogerie"));
+        assert_eq!(expenses[0].amount, Money::new(450, "EUR"));
+        assert_eq!(expenses[0].title, "DM - ");
+        assert_eq!(expenses[1].category, None);
+        assert_ne!(expenses[0].id, expenses[1].id);
     }
 
-    html.push_str("</div>");
-    html
+    #[test]
+    fn test_overlapping_exports_share_fingerprints() {
+        let january = vec![record("REWE", "-4,50"), record("REWE", "-4,50")];
+        let mut overlapping = vec![record("DM", "-1,00")];
+        overlapping.extend(january.clone());
+
+        let fingerprints = |records: &[CsvRecord]| -> Vec<String> {
+            to_expenses(records, &[], &categories())
+                .into_iter()
+                .filter_map(|x| x.fingerprint)
+                .collect()
+        };
+        let first = fingerprints(&january);
+        let second = fingerprints(&overlapping);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(first[..], second[1..]);
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let input = |bank: Option<&str>, path: &str| Input {
+            bank: bank.map(|x| x.to_string()),
+            path: path.to_string(),
+        };
+        assert_eq!("dkb:visa.csv".parse(), Ok(input(Some("dkb"), "visa.csv")));
+        assert_eq!("giro.csv".parse(), Ok(input(None, "giro.csv")));
+        assert_eq!("C:giro.csv".parse(), Ok(input(None, "C:giro.csv")));
+        assert_eq!("n26:main.csv".parse(), Ok(input(Some("n26"), "main.csv")));
+        assert!("dkb:".parse::<Input>().is_err());
+    }
+
+    #[test]
+    fn test_read_input_decodes_windows_1252() {
+        let input: Input = "testdata/dkb_windows1252.csv".parse().unwrap();
+        let records = input.read(&Registry::default(), None).unwrap().records;
+        assert_eq!(records[0].name, "Stadtwerke Bückeburg");
+
+        let error = input
+            .read(&Registry::default(), Some(Encoding::Latin1))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "testdata/dkb_windows1252.csv: Line 3 is not valid iso-8859-1, set another --encoding"
+        );
+    }
+
+    #[test]
+    fn test_read_inputs_merges_and_deduplicates() {
+        let dir = std::env::temp_dir().join(format!("auseinnahmen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let header = "Bezeichnung Auftragskonto;IBAN Auftragskonto;BIC Auftragskonto;Bankname Auftragskonto;Buchungstag;Valutadatum;Name Zahlungsbeteiligter;IBAN Zahlungsbeteiligter;BIC;Buchungstext;Verwendungszweck;Betrag;Waehrung";
+        let line = |date: &str, name: &str, amount: &str| {
+            format!("Giro;DE00;X;GLS;{date};{date};{name};DE11;Y;Lastschrift;;{amount};EUR")
+        };
+        let january = vec![
+            header.to_string(),
+            line("05.01.2026", "Kiosk", "-1,00"),
+            line("05.01.2026", "Kiosk", "-1,00"),
+            line("02.01.2026", "REWE", "-10,00"),
+        ];
+        let overlapping = vec![
+            header.to_string(),
+            line("03.01.2026", "DM", "-2,00"),
+            line("05.01.2026", "Kiosk", "-1,00"),
+            line("05.01.2026", "Kiosk", "-1,00"),
+            line("05.01.2026", "Kiosk", "-1,00"),
+        ];
+        let mut inputs = Vec::new();
+        for (name, lines) in [("january.csv", january), ("overlapping.csv", overlapping)] {
+            let path = dir.join(name);
+            fs::write(&path, lines.join("\n")).unwrap();
+            inputs.push(format!("gls:{}", path.display()).parse::<Input>().unwrap());
+        }
+
+        let result = read_inputs(&inputs, &Registry::default(), None, false).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = result.records.iter().map(|x| &x.name as &str).collect();
+        assert_eq!(names, vec!["REWE", "DM", "Kiosk", "Kiosk", "Kiosk"]);
+        assert!(result.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_read_inputs_strict() {
+        let path = std::env::temp_dir().join(format!("auseinnahmen-{}.csv", std::process::id()));
+        let content = format!(
+            "{}\n;;Kontostand;3.417,50",
+            include_str!("../../testdata/gls.csv").trim_end()
+        );
+        fs::write(&path, content).unwrap();
+        let inputs = vec![path.display().to_string().parse::<Input>().unwrap()];
+
+        let result = read_inputs(&inputs, &Registry::default(), None, false).unwrap();
+        assert_eq!(result.records.len(), 3);
+        assert_eq!(
+            result.skipped,
+            vec![(
+                path.display().to_string(),
+                Skipped {
+                    line: 5,
+                    reason: "4 fields, expected at least 12".to_string()
+                }
+            )]
+        );
+
+        let error = read_inputs(&inputs, &Registry::default(), None, true).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error,
+            format!(
+                "{}: Unexpected line 5: 4 fields, expected at least 12",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert_eq!(parse_date("02.01.2026"), Ok(date));
+        assert_eq!(parse_date("02.01.26"), Ok(date));
+        assert!(parse_date("2026-01-02").is_err());
+        assert_eq!(parse_optional_date(" "), Ok(None));
+    }
+
+    #[test]
+    fn test_expenses_keep_dates() {
+        let mut records = vec![record("REWE", "-4,50"); 30];
+        records[0].value_date = NaiveDate::from_ymd_opt(2026, 2, 3);
+        let expenses = to_expenses(&records, &[], &categories());
+        let ids: std::collections::HashSet<&str> = expenses.iter().map(|x| &x.id as &str).collect();
+        assert_eq!(ids.len(), 30);
+        assert!(
+            expenses
+                .iter()
+                .all(|x| x.date() == NaiveDate::from_ymd_opt(2026, 2, 1))
+        );
+        assert_eq!(expenses[0].value_date, NaiveDate::from_ymd_opt(2026, 2, 3));
+    }
 }
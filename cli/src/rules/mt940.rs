@@ -0,0 +1,270 @@
+use crate::rules::CsvRecord;
+use crate::rules::csv::normalize;
+use crate::rules::importer::{BankImporter, Import, Skipped};
+use ausgabenzettel_core::Money;
+use chrono::{Datelike, NaiveDate};
+
+/// Keywords of SEPA remittance information within the `:86:` field.
+const SEPA_KEYWORDS: &[&str] = &[
+    "EREF+", "KREF+", "MREF+", "CRED+", "DEBT+", "COAM+", "OAMT+", "SVWZ+", "ABWA+", "ABWE+",
+];
+
+/// A field of a statement like `:61:` with the line it starts on.
+struct Field<'a> {
+    line: usize,
+    tag: &'a str,
+    value: String,
+}
+
+/// SWIFT MT940 customer statement as offered by German banks.
+pub struct Mt940;
+
+impl BankImporter for Mt940 {
+    fn name(&self) -> &str {
+        "mt940"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        line.starts_with(":20:")
+    }
+
+    fn parse(&self, content: &str) -> Result<Import, String> {
+        let content = normalize(content);
+        let mut import = Import::default();
+        let mut currency = "EUR".to_string();
+
+        for field in fields(&content) {
+            let error = |e: String| format!("Failed to parse line {}: {}", field.line, e);
+            match field.tag {
+                // opening balance like C260101EUR1000,00
+                "60F" | "60M" => {
+                    if let Some(x) = field.value.get(7..10) {
+                        currency = x.to_string();
+                    }
+                }
+                "61" => import
+                    .records
+                    .push(parse_transaction(&field.value, &currency).map_err(error)?),
+                "86" => match import.records.last_mut() {
+                    Some(record) => read_information(record, &field.value),
+                    None => import.skipped.push(Skipped {
+                        line: field.line,
+                        reason: "Information without statement line".to_string(),
+                    }),
+                },
+                _ => {}
+            }
+        }
+        Ok(import)
+    }
+}
+
+/// Splits a statement into its fields, continuation lines are appended
+/// without separator.
+fn fields(content: &str) -> Vec<Field<'_>> {
+    let mut fields: Vec<Field> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|x| x.split_once(':'))
+            .filter(|(tag, _)| (2..=3).contains(&tag.len()));
+        if let Some((tag, value)) = tag {
+            fields.push(Field {
+                line: i + 1,
+                tag,
+                value: value.to_string(),
+            });
+        } else if line.trim() == "-" || line.starts_with('{') {
+            // end of a statement or SWIFT header blocks
+            continue;
+        } else if let Some(field) = fields.last_mut() {
+            field.value.push_str(line);
+        }
+    }
+    fields
+}
+
+/// Parses a `:61:` statement line like `2601020102DR12,50NDDTNONREF`.
+///
+/// The value date is followed by the optional booking date without year, the
+/// debit or credit mark with optional reversal, the optional third letter of
+/// the currency and the amount.
+fn parse_transaction(value: &str, currency: &str) -> Result<CsvRecord, String> {
+    let invalid = || format!("Invalid statement line '{}'", value);
+    let value_date = value
+        .get(..6)
+        .and_then(|x| NaiveDate::parse_from_str(x, "%y%m%d").ok())
+        .ok_or_else(invalid)?;
+
+    let mut rest = &value[6..];
+    let booking_date = match rest
+        .get(..4)
+        .filter(|x| x.chars().all(|c| c.is_ascii_digit()))
+    {
+        Some(x) => {
+            rest = &rest[4..];
+            booking_date(value_date, x).ok_or_else(invalid)?
+        }
+        None => value_date,
+    };
+
+    let (debit, length) = match rest.get(..2) {
+        Some("RC") => (true, 2),
+        Some("RD") => (false, 2),
+        _ if rest.starts_with('C') => (false, 1),
+        _ if rest.starts_with('D') => (true, 1),
+        _ => return Err(invalid()),
+    };
+    rest = &rest[length..];
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ',')
+        .unwrap_or(rest.len());
+    let amount = Money::parse_with(&rest[..end], currency, ',', None).map_err(|e| e.to_string())?;
+
+    Ok(CsvRecord {
+        booking_date,
+        value_date: Some(value_date),
+        amount: if debit { -amount } else { amount },
+        reference: String::new(),
+        iban: String::new(),
+        name: String::new(),
+        booking_text: String::new(),
+        creditor_id: String::new(),
+        mandate_reference: String::new(),
+        refund_of: None,
+        transfer: false,
+        transfer_pair: None,
+    })
+}
+
+/// The booking date given as `MMDD`, in the year closest to the value date.
+fn booking_date(value_date: NaiveDate, day: &str) -> Option<NaiveDate> {
+    let month = day[..2].parse().ok()?;
+    let day = day[2..].parse().ok()?;
+    (value_date.year() - 1..=value_date.year() + 1)
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .min_by_key(|x| (*x - value_date).num_days().abs())
+}
+
+/// Reads the structured `:86:` field like `105?00LASTSCHRIFT?20SVWZ+Einkauf`
+/// into the record, unstructured ones are taken as reference.
+fn read_information(record: &mut CsvRecord, value: &str) {
+    let Some((_, structured)) = value.split_once('?') else {
+        record.reference = value.trim().to_string();
+        return;
+    };
+
+    let mut remittance = String::new();
+    let mut name = String::new();
+    for part in structured.split('?') {
+        let (code, text) = (part.get(..2).unwrap_or(part), part.get(2..).unwrap_or(""));
+        match code {
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" | "60" | "61"
+            | "62" | "63" => remittance.push_str(text),
+            "00" => record.booking_text = text.trim().to_string(),
+            "31" => record.iban = text.trim().to_string(),
+            "32" | "33" => name.push_str(text),
+            _ => {}
+        }
+    }
+    record.name = name.trim().to_string();
+
+    let sepa = sepa_fields(&remittance);
+    if sepa.is_empty() {
+        record.reference = remittance.trim().to_string();
+    } else {
+        let get = |keyword: &str| {
+            sepa.iter()
+                .find(|(x, _)| *x == keyword)
+                .map(|(_, x)| x.trim().to_string())
+                .unwrap_or_default()
+        };
+        record.reference = get("SVWZ+");
+        record.creditor_id = get("CRED+");
+        record.mandate_reference = get("MREF+");
+    }
+}
+
+/// Splits remittance information like `EREF+1CRED+DE98ZZZ1SVWZ+Einkauf` at
+/// the SEPA keywords.
+fn sepa_fields(remittance: &str) -> Vec<(&'static str, &str)> {
+    let mut starts: Vec<(usize, &'static str)> = SEPA_KEYWORDS
+        .iter()
+        .flat_map(|keyword| {
+            remittance
+                .match_indices(keyword)
+                .map(|(i, _)| (i, *keyword))
+        })
+        .collect();
+    starts.sort();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, keyword))| {
+            let end = starts.get(i + 1).map_or(remittance.len(), |(x, _)| *x);
+            (*keyword, &remittance[start + keyword.len()..end])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture() {
+        let records = Mt940
+            .parse(include_str!("../../testdata/mt940.sta"))
+            .unwrap()
+            .records;
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0].booking_date,
+            NaiveDate::from_ymd_opt(2026, 1, 2).unwrap()
+        );
+        assert_eq!(records[0].value_date, NaiveDate::from_ymd_opt(2026, 1, 3));
+        assert_eq!(records[0].amount, Money::new(-1250, "EUR"));
+        assert_eq!(records[0].name, "REWE Markt");
+        assert_eq!(records[0].iban, "DE12500105170648489890");
+        assert_eq!(records[0].booking_text, "SEPA-BASISLASTSCHRIFT");
+        assert_eq!(records[0].creditor_id, "DE98ZZZ09999999999");
+        assert_eq!(records[0].mandate_reference, "M-4711");
+        assert_eq!(records[0].reference, "Einkauf Filiale 12 Pfand");
+
+        assert_eq!(records[1].amount, Money::new(245000, "EUR"));
+        assert_eq!(records[1].name, "Arbeitgeber GmbH");
+        assert_eq!(records[1].reference, "Gehalt Januar");
+
+        // booked in the next year, reversal of a debit
+        assert_eq!(
+            records[2].booking_date,
+            NaiveDate::from_ymd_opt(2027, 1, 2).unwrap()
+        );
+        assert_eq!(records[2].amount, Money::new(500, "EUR"));
+        assert_eq!(records[2].reference, "Rueckbuchung");
+    }
+
+    #[test]
+    fn test_parse_reports_information_without_statement_line() {
+        let import = Mt940.parse(":20:STARTUMS\n:86:Kontoauszug\n").unwrap();
+        assert!(import.records.is_empty());
+        assert_eq!(
+            import.skipped,
+            vec![Skipped {
+                line: 2,
+                reason: "Information without statement line".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_statement_line_has_line_number() {
+        let content = include_str!("../../testdata/mt940.sta").replace("DR12,50", "X12,50");
+        let error = Mt940.parse(&content).unwrap_err();
+        assert!(error.starts_with("Failed to parse line 5:"), "{}", error);
+    }
+}
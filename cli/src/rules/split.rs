@@ -0,0 +1,206 @@
+use crate::rules::condition::Amount;
+use ausgabenzettel_core::Money;
+use serde::{Deserialize, Serialize};
+
+/// Part of a transaction going to another category than the one of the rule,
+/// e.g. `{"category": "drogerie", "percent": 20}`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Split {
+    pub category: String,
+    #[serde(flatten)]
+    pub share: Share,
+}
+
+/// Fixed amount or percentage of the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Share {
+    Amount(Amount),
+    Percent(u32),
+}
+
+impl std::str::FromStr for Share {
+    type Err = String;
+
+    /// Parses shares as entered at the prompt, `20%` or `5,00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            return percent
+                .trim()
+                .parse()
+                .map(Share::Percent)
+                .map_err(|_| format!("Invalid percentage '{}', expected e.g. 20%", s));
+        }
+        Money::parse_german(s, "")
+            .map(|x| Share::Amount(Amount(x.minor)))
+            .map_err(|_| format!("Invalid amount '{}', expected e.g. 5,00 or 20%", s))
+    }
+}
+
+/// Checks that the shares are positive and the percentages add up to at most
+/// 100.
+pub fn validate(splits: &[Split]) -> Result<(), String> {
+    let mut percent: u32 = 0;
+    for split in splits {
+        match split.share {
+            Share::Amount(Amount(x)) if x <= 0 => {
+                return Err(format!(
+                    "Split amount for {} must be positive",
+                    split.category
+                ));
+            }
+            Share::Percent(0) => {
+                return Err(format!(
+                    "Split percentage for {} must be positive",
+                    split.category
+                ));
+            }
+            Share::Percent(x) => {
+                percent = percent
+                    .checked_add(x)
+                    .ok_or_else(|| "Split percentages add up to more than 100%".to_string())?;
+            }
+            Share::Amount(_) => {}
+        }
+    }
+    if percent > 100 {
+        return Err(format!("Split percentages add up to {}%", percent));
+    }
+    Ok(())
+}
+
+/// Splits the amount into the rest for the category of the rule followed by
+/// the share of each split, all with the sign of `amount`.
+///
+/// Percentages are of the whole amount and rounded to the nearest minor unit.
+/// Shares are capped to what the previous ones left, so the parts always add
+/// up to the amount.
+pub fn split_amount(amount: &Money, splits: &[Split]) -> Vec<Money> {
+    let total = amount.minor.abs();
+    let sign = amount.minor.signum();
+    let mut rest = total;
+    let mut parts = vec![Money::zero(&amount.currency)];
+    for split in splits {
+        let share = match split.share {
+            Share::Amount(Amount(x)) => x,
+            Share::Percent(x) => (total * i64::from(x) + 50) / 100,
+        }
+        .min(rest);
+        rest -= share;
+        parts.push(Money::new(sign * share, &amount.currency));
+    }
+    parts[0] = Money::new(sign * rest, &amount.currency);
+    parts
+}
+
+/// Distributes `amount` in proportion to the parts, with the sign of
+/// `amount`, e.g. a partial refund like the split of the refunded expense.
+///
+/// Rounding the running sum keeps the total at `amount`. Without parts to
+/// scale the first part gets all of it.
+pub fn scale(parts: &[Money], amount: &Money) -> Vec<Money> {
+    let total: i128 = parts.iter().map(|x| i128::from(x.minor.abs())).sum();
+    let target = i128::from(amount.minor.abs());
+    let sign = amount.minor.signum();
+    let mut sum = 0;
+    let mut done = 0;
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            sum += i128::from(part.minor.abs());
+            let upto = match total {
+                0 if i == 0 => target,
+                0 => done,
+                _ => (target * sum * 2 + total) / (total * 2),
+            };
+            let share = (upto - done) as i64;
+            done = upto;
+            Money::new(sign * share, &amount.currency)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(category: &str, share: Share) -> Split {
+        Split {
+            category: category.to_string(),
+            share,
+        }
+    }
+
+    #[test]
+    fn test_split_amount() {
+        let splits = vec![
+            split("drogerie", Share::Amount(Amount(500))),
+            split("haushalt", Share::Percent(33)),
+        ];
+        let minor = |amount: i64| -> Vec<i64> {
+            split_amount(&Money::new(amount, "EUR"), &splits)
+                .into_iter()
+                .map(|x| x.minor)
+                .collect()
+        };
+        assert_eq!(minor(-2001), vec![-841, -500, -660]);
+        assert_eq!(minor(3000), vec![1510, 500, 990]);
+        // fixed amounts larger than the transaction take all of it
+        assert_eq!(minor(-300), vec![0, -300, 0]);
+    }
+
+    #[test]
+    fn test_scale() {
+        let parts = [2200, 500, 300].map(|x| Money::new(-x, "EUR"));
+        let minor = |amount: i64| -> Vec<i64> {
+            scale(&parts, &Money::new(amount, "EUR"))
+                .into_iter()
+                .map(|x| x.minor)
+                .collect()
+        };
+        assert_eq!(minor(600), vec![440, 100, 60]);
+        assert_eq!(minor(-3000), vec![-2200, -500, -300]);
+        // rounding keeps the sum
+        assert_eq!(minor(1), vec![1, 0, 0]);
+        assert_eq!(minor(7), vec![5, 1, 1]);
+        assert_eq!(
+            scale(&[Money::zero("EUR")], &Money::new(100, "EUR")),
+            vec![Money::new(100, "EUR")]
+        );
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        assert_eq!("20 %".parse(), Ok(Share::Percent(20)));
+        assert_eq!("1.234,5".parse(), Ok(Share::Amount(Amount(123450))));
+        assert!("x%".parse::<Share>().is_err());
+
+        let splits: Vec<Split> = serde_json::from_str(
+            r#"[{"category": "drogerie", "amount": "5.00"}, {"category": "haushalt", "percent": 60}]"#,
+        )
+        .unwrap();
+        assert_eq!(splits[0].share, Share::Amount(Amount(500)));
+        assert!(validate(&splits).is_ok());
+        assert_eq!(
+            serde_json::to_string(&splits[1]).unwrap(),
+            r#"{"category":"haushalt","percent":60}"#
+        );
+
+        let too_much = vec![
+            split("drogerie", Share::Percent(60)),
+            split("haushalt", Share::Percent(50)),
+        ];
+        assert_eq!(
+            validate(&too_much).unwrap_err(),
+            "Split percentages add up to 110%"
+        );
+        assert!(validate(&[split("drogerie", Share::Amount(Amount(0)))]).is_err());
+        let overflow = vec![
+            split("drogerie", Share::Percent(u32::MAX)),
+            split("haushalt", Share::Percent(2)),
+        ];
+        assert!(validate(&overflow).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::rules::{CsvRecord, normalize_iban};
+use ausgabenzettel_core::Kind;
+use std::fs;
+
+/// Days between the bookings on both accounts of a transfer.
+const MAX_DAYS: i64 = 5;
+
+/// Reads the JSON array of own IBANs, e.g. `["DE11 2233 ...", "DE44 ..."]`.
+pub fn load_own_ibans(path: &str) -> Result<Vec<String>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read own IBANs file: {}", e))?;
+    let ibans: Vec<String> =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse own IBANs: {}", e))?;
+    Ok(ibans.iter().map(|x| normalize_iban(x)).collect())
+}
+
+/// Marks records whose counterparty is one of the own accounts as transfers.
+///
+/// Returns the number of transfers.
+pub fn classify(records: &mut [CsvRecord], own_ibans: &[String]) -> usize {
+    let mut transfers = 0;
+    for record in records {
+        let iban = normalize_iban(&record.iban);
+        if !iban.is_empty() && own_ibans.contains(&iban) {
+            record.transfer = true;
+            transfers += 1;
+        }
+    }
+    transfers
+}
+
+/// Pairs outgoing transfers with incoming ones of the same amount booked at
+/// most [`MAX_DAYS`] apart, usually found within the exports of both accounts.
+///
+/// Returns the number of pairs.
+pub fn pair(records: &mut [CsvRecord]) -> usize {
+    let transfers: Vec<usize> = (0..records.len())
+        .filter(|&i| records[i].kind() == Kind::Transfer)
+        .collect();
+    let mut outgoing: Vec<usize> = transfers
+        .iter()
+        .copied()
+        .filter(|&i| records[i].amount.is_negative())
+        .collect();
+    outgoing.sort_by_key(|&i| records[i].booking_date);
+
+    let mut pairs = 0;
+    for i in outgoing {
+        let best = transfers
+            .iter()
+            .copied()
+            .filter(|&j| {
+                let incoming = &records[j];
+                incoming.transfer_pair.is_none()
+                    && incoming.amount == -records[i].amount.clone()
+                    && (incoming.booking_date - records[i].booking_date)
+                        .num_days()
+                        .abs()
+                        <= MAX_DAYS
+            })
+            .min_by_key(|&j| {
+                (records[j].booking_date - records[i].booking_date)
+                    .num_days()
+                    .abs()
+            });
+        if let Some(j) = best {
+            records[i].transfer_pair = Some(j);
+            records[j].transfer_pair = Some(i);
+            pairs += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn record(day: u32, iban: &str, amount: &str) -> CsvRecord {
+        CsvRecord {
+            booking_date: NaiveDate::from_ymd_opt(2026, 2, day).unwrap(),
+            iban: iban.to_string(),
+            ..CsvRecord::test("", amount)
+        }
+    }
+
+    #[test]
+    fn test_classify_and_pair_across_exports() {
+        let own = vec![normalize_iban("DE11 2233"), normalize_iban("DE44 5566")];
+        // giro export followed by the one of the credit card account
+        let mut records = vec![
+            record(1, "DE44 5566", "-100,00"),
+            record(2, "DE99", "-100,00"),
+            record(9, "DE44 5566", "-50,00"),
+            record(3, "DE112233", "100,00"),
+            record(20, "DE112233", "50,00"),
+        ];
+
+        assert_eq!(classify(&mut records, &own), 4);
+        assert!(!records[1].transfer);
+        assert_eq!(pair(&mut records), 1);
+        assert_eq!(records[0].transfer_pair, Some(3));
+        assert_eq!(records[3].transfer_pair, Some(0));
+        // too far apart to be the same transfer
+        assert_eq!(records[2].transfer_pair, None);
+        assert_eq!(records[4].transfer_pair, None);
+    }
+}
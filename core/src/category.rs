@@ -0,0 +1,276 @@
+//! Categories as defined in the `categories.json` shared by all components.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Kind;
+
+/// Class used for entries without a known category.
+pub const UNKNOWN_CLASS: &str = "cat-unknown";
+/// Class the frontend uses for income without a known category.
+pub const UNKNOWN_INCOME_CLASS: &str = "cat-unknown-income";
+/// Prefix of the class carrying the category id of an entry.
+const CLASS_PREFIX: &str = "cat-";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid category id '{0}', only a-z, 0-9 and '-' are allowed")]
+    InvalidId(String),
+    #[error("duplicate category id '{0}'")]
+    DuplicateId(String),
+    #[error("category '{0}' has unknown parent '{1}'")]
+    UnknownParent(String, String),
+    #[error("category '{0}' is its own ancestor")]
+    Cycle(String),
+    #[error("category '{0}' must be of the same kind as its parent '{1}'")]
+    KindMismatch(String, String),
+    #[error("category '{0}' can not be of kind transfer, transfers are not categorized")]
+    TransferKind(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Category {
+    /// Stable identifier, referenced by lookup rules and used as CSS class `cat-{id}`.
+    pub id: String,
+    /// Position before ids were introduced, only needed for migrating.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    pub title: String,
+    pub description: String,
+    /// Income categories group received money, e.g. salary.
+    #[serde(default, skip_serializing_if = "Kind::is_expense")]
+    pub kind: Kind,
+    /// Id of the category this one is a subcategory of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// CSS colour the backend uses for this category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl Category {
+    /// A top level expense category without description, further fields can
+    /// be set with struct update syntax.
+    pub fn new(id: &str, title: &str) -> Self {
+        Category {
+            id: id.to_string(),
+            index: None,
+            title: title.to_string(),
+            description: String::new(),
+            kind: Kind::Expense,
+            parent: None,
+            color: None,
+        }
+    }
+
+    /// CSS class used for entries of this category.
+    pub fn class(&self) -> String {
+        format!("{CLASS_PREFIX}{}", self.id)
+    }
+}
+
+/// Returns true when the id can be used within a CSS class name.
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Class of entries of the kind without a known category, like the frontend
+/// assigns them.
+pub fn unknown_class(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Income => UNKNOWN_INCOME_CLASS,
+        Kind::Expense | Kind::Transfer => UNKNOWN_CLASS,
+    }
+}
+
+/// Whether the class marks an entry without a known category.
+pub fn is_unknown_class(class: &str) -> bool {
+    class == UNKNOWN_CLASS || class == UNKNOWN_INCOME_CLASS
+}
+
+/// Extracts the category id from a class like `cat-alltag`.
+///
+/// Returns None for other classes, including legacy ones like `cat1`.
+pub fn id_from_class(class: &str) -> Option<&str> {
+    class.strip_prefix(CLASS_PREFIX).filter(|x| is_valid_id(x))
+}
+
+/// Parses and validates the content of a categories file.
+pub fn parse(content: &str) -> Result<Vec<Category>, Error> {
+    let categories: Vec<Category> = serde_json::from_str(content)?;
+    for (i, category) in categories.iter().enumerate() {
+        if !is_valid_id(&category.id) {
+            return Err(Error::InvalidId(category.id.clone()));
+        }
+        if categories[..i].iter().any(|x| x.id == category.id) {
+            return Err(Error::DuplicateId(category.id.clone()));
+        }
+        if category.kind == Kind::Transfer {
+            return Err(Error::TransferKind(category.id.clone()));
+        }
+    }
+    for category in &categories {
+        if let Some(parent) = &category.parent {
+            match categories.iter().find(|x| &x.id == parent) {
+                None => return Err(Error::UnknownParent(category.id.clone(), parent.clone())),
+                Some(x) if x.kind != category.kind => {
+                    return Err(Error::KindMismatch(category.id.clone(), parent.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        if ancestors(&categories, category).len() > categories.len() {
+            return Err(Error::Cycle(category.id.clone()));
+        }
+    }
+    Ok(categories)
+}
+
+/// Returns the categories depth first, each with its depth below the top level.
+pub fn tree(categories: &[Category]) -> Vec<(usize, &Category)> {
+    fn add<'a>(
+        categories: &'a [Category],
+        parent: Option<&str>,
+        depth: usize,
+        result: &mut Vec<(usize, &'a Category)>,
+    ) {
+        for category in categories.iter().filter(|x| x.parent.as_deref() == parent) {
+            result.push((depth, category));
+            add(categories, Some(&category.id), depth + 1, result);
+        }
+    }
+
+    let mut result = Vec::with_capacity(categories.len());
+    add(categories, None, 0, &mut result);
+    result
+}
+
+/// Returns the category followed by its parents up to the top level.
+///
+/// Stops after one more element than there are categories so that cycles
+/// can be detected.
+pub fn ancestors<'a>(categories: &'a [Category], category: &'a Category) -> Vec<&'a Category> {
+    let mut result = vec![category];
+    let mut current = category;
+    while let Some(parent) = &current.parent
+        && result.len() <= categories.len()
+    {
+        match categories.iter().find(|x| &x.id == parent) {
+            Some(x) => {
+                result.push(x);
+                current = x;
+            }
+            None => break,
+        }
+    }
+    result
+}
+
+/// Title of the category including its parents, e.g. `Alltag / Drogerie`.
+pub fn path(categories: &[Category], category: &Category) -> String {
+    let mut titles: Vec<&str> = ancestors(categories, category)
+        .iter()
+        .map(|x| &x.title as &str)
+        .collect();
+    titles.reverse();
+    titles.join(" / ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: &str, parent: Option<&str>) -> Category {
+        Category {
+            parent: parent.map(|x| x.to_string()),
+            ..Category::new(id, id)
+        }
+    }
+
+    fn categories() -> Vec<Category> {
+        vec![
+            category("alltag", None),
+            category("ausgehen", None),
+            category("drogerie", Some("alltag")),
+        ]
+    }
+
+    #[test]
+    fn test_tree() {
+        let categories = categories();
+        let tree: Vec<(usize, &str)> = tree(&categories)
+            .into_iter()
+            .map(|(depth, x)| (depth, &x.id as &str))
+            .collect();
+        assert_eq!(tree, vec![(0, "alltag"), (1, "drogerie"), (0, "ausgehen")]);
+    }
+
+    #[test]
+    fn test_path() {
+        let categories = categories();
+        assert_eq!(path(&categories, &categories[2]), "alltag / drogerie");
+    }
+
+    #[test]
+    fn test_ancestors_stops_on_cycle() {
+        let categories = vec![category("a", Some("b")), category("b", Some("a"))];
+        assert!(ancestors(&categories, &categories[0]).len() > categories.len());
+    }
+
+    #[test]
+    fn test_id_from_class() {
+        assert_eq!(id_from_class("cat-alltag"), Some("alltag"));
+        assert_eq!(
+            id_from_class("cat-beauty-wellness"),
+            Some("beauty-wellness")
+        );
+        assert_eq!(id_from_class("cat1"), None);
+        assert_eq!(id_from_class("cat-"), None);
+        assert_eq!(id_from_class("other"), None);
+        assert_eq!(id_from_class("cat-X"), None);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut categories = categories();
+        categories[0].color = Some("#f7910c".to_string());
+        categories[1].index = Some(1);
+        categories[1].kind = Kind::Income;
+        let json = serde_json::to_string(&categories).unwrap();
+        assert_eq!(parse(&json).unwrap(), categories);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_parents() {
+        let unknown = r#"[{"id": "drogerie", "parent": "alltag", "title": "", "description": ""}]"#;
+        assert!(matches!(parse(unknown), Err(Error::UnknownParent(_, _))));
+        let cycle = r#"[
+            {"id": "a", "parent": "b", "title": "", "description": ""},
+            {"id": "b", "parent": "a", "title": "", "description": ""}
+        ]"#;
+        assert!(matches!(parse(cycle), Err(Error::Cycle(_))));
+        let kind = r#"[
+            {"id": "einkommen", "kind": "income", "title": "", "description": ""},
+            {"id": "gehalt", "parent": "einkommen", "title": "", "description": ""}
+        ]"#;
+        assert!(matches!(parse(kind), Err(Error::KindMismatch(_, _))));
+        let transfer =
+            r#"[{"id": "umbuchung", "kind": "transfer", "title": "", "description": ""}]"#;
+        assert!(matches!(parse(transfer), Err(Error::TransferKind(_))));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_ids() {
+        let invalid = r#"[{"id": "Beauty & Wellness", "title": "", "description": ""}]"#;
+        assert!(matches!(parse(invalid), Err(Error::InvalidId(_))));
+        let duplicate = r#"[
+            {"id": "alltag", "title": "", "description": ""},
+            {"id": "alltag", "title": "", "description": ""}
+        ]"#;
+        assert!(matches!(parse(duplicate), Err(Error::DuplicateId(_))));
+    }
+}
@@ -0,0 +1,671 @@
+//! Reading and writing the `#details` content stored by the backend.
+//!
+//! Each expense is stored like the frontend renders it:
+//!
+//! ```html
+//! <div id="details">
+//!   <details class="cat-alltag" id="1767355200000" data-booking-date="2026-01-02">
+//!     <summary><span>Groceries</span><span>12.00€</span></summary>
+//!     <div>
+//!       <div class="row"><span>Category</span><span class="right-align">Alltag</span></div>
+//!       <a href="#">remove</a>
+//!     </div>
+//!   </details>
+//! </div>
+//! ```
+//!
+//! Imported bank transactions additionally carry `data-value-date` and
+//! `data-fingerprint`. Income is marked with `data-kind="income"`, its amount
+//! is positive like the one of expenses. Refunds additionally carry the id of
+//! the refunded expense as `data-refund-of`. Transfers between own accounts are
+//! marked with `data-kind="transfer"`, keep the sign of the bank transaction
+//! and reference the entry of the other account as `data-transfer-pair`.
+//! A bank transaction split across categories becomes one entry per category,
+//! all but the first reference the first one as `data-split-of`.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use quick_xml::{
+    Reader,
+    escape::{escape, unescape},
+    events::{BytesStart, Event},
+};
+
+use crate::{
+    Expense, Kind, Money,
+    category::{self, Category},
+    money::DEFAULT_CURRENCY,
+};
+
+/// Title of the category row for entries without a known category.
+const UNKNOWN_TITLE: &str = "Unknown";
+
+/// Format of dates within attributes, as used by `<input type="date">`.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT).ok()
+}
+
+/// Creates an expense from the attributes of a `<details>` element.
+fn start_expense(e: &BytesStart) -> Expense {
+    let mut expense = Expense {
+        id: String::new(),
+        kind: Kind::Expense,
+        booking_date: None,
+        value_date: None,
+        category: None,
+        title: String::new(),
+        amount: Money::zero(DEFAULT_CURRENCY),
+        fingerprint: None,
+        refund_of: None,
+        transfer_pair: None,
+        split_of: None,
+    };
+    for attr in e.attributes().flatten() {
+        let value = attr
+            .unescape_value()
+            .map(|x| x.to_string())
+            .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
+        match attr.key.as_ref() {
+            b"id" => expense.id = value,
+            b"data-kind" => expense.kind = value.parse().unwrap_or_default(),
+            b"data-fingerprint" => expense.fingerprint = Some(value),
+            b"data-refund-of" => expense.refund_of = Some(value),
+            b"data-transfer-pair" => expense.transfer_pair = Some(value),
+            b"data-split-of" => expense.split_of = Some(value),
+            b"data-booking-date" => expense.booking_date = parse_date(&value),
+            b"data-value-date" => expense.value_date = parse_date(&value),
+            b"class" => {
+                expense.category = value
+                    .split_whitespace()
+                    .filter(|x| !category::is_unknown_class(x))
+                    .find_map(category::id_from_class)
+                    .map(|x| x.to_string())
+            }
+            _ => {}
+        }
+    }
+    expense
+}
+
+/// The expenses of a document and the problems found while parsing it.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub expenses: Vec<Expense>,
+    /// Invalid amounts, which are read as zero, and parser errors, which end
+    /// the parsing.
+    pub warnings: Vec<String>,
+}
+
+/// The merged document of [`merge`].
+#[derive(Debug)]
+pub struct Merged {
+    pub html: String,
+    /// Number of added expenses.
+    pub added: usize,
+    /// Problems of the existing document, see [`Parsed`].
+    pub warnings: Vec<String>,
+}
+
+/// Parses all expenses of a document, entries without an id are skipped.
+pub fn parse(html: &str) -> Parsed {
+    let mut reader = Reader::from_str(html);
+    let mut parsed = Parsed::default();
+
+    let mut current: Option<Expense> = None;
+    let mut in_summary = false;
+    let mut summary_span_count = 0;
+    // escaped text of the currently open span within the summary
+    let mut span: Option<String> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.name().as_ref() {
+                b"details" => {
+                    current = Some(start_expense(&e));
+                    summary_span_count = 0;
+                }
+                b"summary" if current.is_some() => in_summary = true,
+                b"span" if in_summary => {
+                    summary_span_count += 1;
+                    span = Some(String::new());
+                }
+                _ => {}
+            },
+            Ok(Event::Text(e)) => {
+                if let Some(text) = span.as_mut() {
+                    text.push_str(&String::from_utf8_lossy(&e));
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                if let Some(text) = span.as_mut() {
+                    text.push('&');
+                    text.push_str(&String::from_utf8_lossy(&e));
+                    text.push(';');
+                }
+            }
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"span" => {
+                    if let (Some(raw), Some(expense)) = (span.take(), current.as_mut()) {
+                        let text = unescape(&raw).map(|x| x.to_string()).unwrap_or(raw);
+                        match summary_span_count {
+                            1 => expense.title = text,
+                            2 => {
+                                expense.amount = text.parse().unwrap_or_else(|e| {
+                                    parsed
+                                        .warnings
+                                        .push(format!("Invalid amount of {}: {}", expense.id, e));
+                                    Money::zero(DEFAULT_CURRENCY)
+                                });
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                b"summary" => in_summary = false,
+                b"details" => {
+                    if let Some(expense) = current.take()
+                        && !expense.id.is_empty()
+                    {
+                        parsed.expenses.push(expense);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                parsed.warnings.push(format!(
+                    "Parser error at pos {}: {}",
+                    reader.buffer_position(),
+                    e
+                ));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    parsed
+}
+
+/// Renders a single expense as `<details>` element.
+pub fn render_expense(expense: &Expense, categories: &[Category]) -> String {
+    let category = expense
+        .category
+        .as_ref()
+        .and_then(|id| categories.iter().find(|x| &x.id == id));
+    let class = match &expense.category {
+        Some(id) if category::is_valid_id(id) => format!("cat-{id}"),
+        // refunds are counted within the expenses
+        _ if expense.is_refund() => category::unknown_class(Kind::Expense).to_string(),
+        _ => category::unknown_class(expense.kind).to_string(),
+    };
+    let category_title = category
+        .map(|x| category::path(categories, x))
+        .unwrap_or_else(|| UNKNOWN_TITLE.to_string());
+
+    let mut attributes = String::new();
+    if !expense.kind.is_expense() {
+        attributes.push_str(&format!(" data-kind=\"{}\"", expense.kind.as_str()));
+    }
+    for (name, date) in [
+        ("data-booking-date", expense.booking_date),
+        ("data-value-date", expense.value_date),
+    ] {
+        if let Some(date) = date {
+            attributes.push_str(&format!(" {name}=\"{}\"", date.format(DATE_FORMAT)));
+        }
+    }
+    if let Some(fingerprint) = &expense.fingerprint {
+        attributes.push_str(&format!(" data-fingerprint=\"{}\"", escape(fingerprint)));
+    }
+    if let Some(refund_of) = &expense.refund_of {
+        attributes.push_str(&format!(" data-refund-of=\"{}\"", escape(refund_of)));
+    }
+    if let Some(pair) = &expense.transfer_pair {
+        attributes.push_str(&format!(" data-transfer-pair=\"{}\"", escape(pair)));
+    }
+    if let Some(split_of) = &expense.split_of {
+        attributes.push_str(&format!(" data-split-of=\"{}\"", escape(split_of)));
+    }
+
+    format!(
+        "<details class=\"{class}\" id=\"{}\"{attributes}>\
+         <summary><span>{}</span><span>{}</span></summary>\
+         <div><div class=\"row\"><span>Category</span><span class=\"right-align\">{}</span></div>\
+         <a href=\"#\">remove</a></div>\
+         </details>",
+        escape(&expense.id),
+        escape(&expense.title),
+        escape(expense.amount.to_string()),
+        escape(&category_title)
+    )
+}
+
+/// Renders the expenses as `#details` element.
+pub fn render(expenses: &[Expense], categories: &[Category]) -> String {
+    let mut html = String::from("<div id=\"details\">");
+    for expense in expenses {
+        html.push_str(&render_expense(expense, categories));
+    }
+    html.push_str("</div>");
+    html
+}
+
+/// Returns the id itself or, when already taken, the next free timestamp.
+fn free_id(id: &str, ids: &HashSet<String>) -> String {
+    let Ok(mut timestamp) = id.parse::<u64>() else {
+        return id.to_string();
+    };
+    while ids.contains(&timestamp.to_string()) {
+        timestamp += 1;
+    }
+    timestamp.to_string()
+}
+
+/// Adds the expenses to an existing document, skipping those already present.
+///
+/// Expenses with a fingerprint are skipped when an entry with the same
+/// fingerprint exists, otherwise when one with the same id exists. Ids taken
+/// by other entries are moved to the next free millisecond.
+///
+/// References of the added expenses to others of the batch (`refund_of`,
+/// `transfer_pair` and `split_of`) follow them: to the moved id, or to the id
+/// of the existing entry when the referenced expense was skipped. References
+/// to ids outside of the batch are kept.
+///
+/// Existing entries are kept as they are, new ones are appended to `#details`.
+pub fn merge(html: &str, expenses: &[Expense], categories: &[Category]) -> Merged {
+    let Parsed {
+        expenses: existing,
+        warnings,
+    } = parse(html);
+    let mut ids: HashSet<String> = existing.iter().map(|x| x.id.clone()).collect();
+    let mut fingerprints: HashMap<String, String> = existing
+        .into_iter()
+        .filter_map(|x| Some((x.fingerprint?, x.id)))
+        .collect();
+
+    // id of each expense of the batch within the merged document
+    let mut stored: HashMap<String, String> = HashMap::new();
+    let mut added = Vec::new();
+    for expense in expenses {
+        let known = match &expense.fingerprint {
+            Some(fingerprint) => fingerprints.get(fingerprint).cloned(),
+            None => ids.contains(&expense.id).then(|| expense.id.clone()),
+        };
+        if let Some(id) = known {
+            stored.entry(expense.id.clone()).or_insert(id);
+            continue;
+        }
+        let mut added_expense = expense.clone();
+        added_expense.id = free_id(&expense.id, &ids);
+        ids.insert(added_expense.id.clone());
+        if let Some(fingerprint) = &expense.fingerprint {
+            fingerprints.insert(fingerprint.clone(), added_expense.id.clone());
+        }
+        stored
+            .entry(expense.id.clone())
+            .or_insert_with(|| added_expense.id.clone());
+        added.push(added_expense);
+    }
+    for expense in &mut added {
+        for reference in [
+            &mut expense.refund_of,
+            &mut expense.transfer_pair,
+            &mut expense.split_of,
+        ] {
+            if let Some(id) = reference.as_ref().and_then(|x| stored.get(x)) {
+                *reference = Some(id.clone());
+            }
+        }
+    }
+
+    let rendered: String = added
+        .iter()
+        .map(|x| render_expense(x, categories))
+        .collect();
+    let html = match html.rfind("</div>") {
+        Some(end) => format!("{}{rendered}{}", &html[..end], &html[end..]),
+        None => format!("<div id=\"details\">{rendered}</div>"),
+    };
+    Merged {
+        html,
+        added: added.len(),
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INITIAL_HTML: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/testdata/initial.html"
+    ));
+
+    fn expense(id: &str, category: Option<&str>, title: &str, amount: Money) -> Expense {
+        Expense {
+            id: id.to_string(),
+            kind: Kind::Expense,
+            booking_date: None,
+            value_date: None,
+            category: category.map(|x| x.to_string()),
+            title: title.to_string(),
+            amount,
+            fingerprint: None,
+            refund_of: None,
+            transfer_pair: None,
+            split_of: None,
+        }
+    }
+
+    fn categories() -> Vec<Category> {
+        category::parse(
+            r#"[
+                {"id": "alltag", "title": "Alltag", "description": ""},
+                {"id": "drogerie", "title": "Drogerie", "description": "", "parent": "alltag"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_full() {
+        let expected = vec![
+            expense(
+                "1767380618000",
+                Some("alltag"),
+                "Groceries",
+                Money::new(1200, "EUR"),
+            ),
+            expense(
+                "1767381117000",
+                Some("reisen"),
+                "Transportation",
+                Money::new(1200, "EUR"),
+            ),
+        ];
+        assert_eq!(parse(INITIAL_HTML).expenses, expected);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(parse("").expenses.is_empty());
+    }
+
+    #[test]
+    fn test_missing_or_unknown_class() {
+        let html = r#"<details id="123"><summary><span>Test</span><span>10€</span></summary></details>
+<details class="cat-unknown" id="124"><summary><span>Test</span><span>10€</span></summary></details>
+<details class="cat-Reisen" id="125"><summary><span>Test</span><span>10€</span></summary></details>"#;
+        let expenses = parse(html).expenses;
+        assert_eq!(expenses.len(), 3);
+        assert!(expenses.iter().all(|x| x.category.is_none()));
+        assert_eq!(expenses[0].title, "Test");
+        assert_eq!(expenses[0].amount, Money::new(1000, "EUR"));
+    }
+
+    #[test]
+    fn test_parse_reports_warnings() {
+        let html = r#"<details class="cat-alltag" id="1"><summary><span>Test</span><span>zehn</span></summary></details>
+<details class="cat-alltag" id="2"><summary><span>Test</span><span>10€</span></summary></details>
+<details id="3"></summary>"#;
+        let parsed = parse(html);
+        assert_eq!(parsed.expenses.len(), 2);
+        assert_eq!(parsed.expenses[0].amount, Money::zero("EUR"));
+        assert_eq!(parsed.warnings.len(), 2);
+        assert!(parsed.warnings[0].starts_with("Invalid amount of 1:"));
+        assert!(parsed.warnings[1].starts_with("Parser error at pos"));
+    }
+
+    #[test]
+    fn test_unknown_income_class() {
+        let html = r#"<details class="cat-unknown-income" id="1" data-kind="income"><summary><span>Gehalt</span><span>10€</span></summary></details>"#;
+        let expenses = parse(html).expenses;
+        assert_eq!(expenses[0].kind, Kind::Income);
+        assert_eq!(expenses[0].category, None);
+        assert!(render(&expenses, &categories()).contains("class=\"cat-unknown-income\""));
+    }
+
+    #[test]
+    fn test_amounts_are_exact() {
+        let html = r#"<details class="cat-alltag" id="1"><summary><span>Test</span><span>1.234,56€</span></summary></details>
+<details class="cat-alltag" id="2"><summary><span>Test</span><span>0.10USD</span></summary></details>"#;
+        let expenses = parse(html).expenses;
+        assert_eq!(expenses[0].amount, Money::new(123456, "EUR"));
+        assert_eq!(expenses[1].amount, Money::new(10, "USD"));
+    }
+
+    #[test]
+    fn test_render() {
+        let html = render(
+            &[expense("1", Some("drogerie"), "DM", Money::new(450, "EUR"))],
+            &categories(),
+        );
+        assert_eq!(
+            html,
+            "<div id=\"details\"><details class=\"cat-drogerie\" id=\"1\">\
+             <summary><span>DM</span><span>4.50€</span></summary>\
+             <div><div class=\"row\"><span>Category</span><span class=\"right-align\">Alltag / Drogerie</span></div>\
+             <a href=\"#\">remove</a></div></details></div>"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let expenses = vec![
+            expense(
+                "1",
+                Some("alltag"),
+                "REWE - Einkauf",
+                Money::new(1050, "EUR"),
+            ),
+            expense(
+                "2",
+                Some("drogerie"),
+                "Tom & Jerry <GmbH>",
+                Money::new(-5, "EUR"),
+            ),
+            Expense {
+                kind: Kind::Income,
+                booking_date: parse_date("2026-01-02"),
+                value_date: parse_date("2026-01-03"),
+                fingerprint: Some("ece83ee4ca2cfd95".to_string()),
+                refund_of: Some("1".to_string()),
+                ..expense("3", None, "Kiosk", Money::new(123456, "USD"))
+            },
+            expense("4", Some("removed"), "Old", Money::new(100, "EUR")),
+            Expense {
+                kind: Kind::Transfer,
+                transfer_pair: Some("6".to_string()),
+                ..expense("5", None, "DKB", Money::new(-10000, "EUR"))
+            },
+            Expense {
+                fingerprint: Some("ece83ee4ca2cfd95-1".to_string()),
+                split_of: Some("3".to_string()),
+                ..expense("7", Some("drogerie"), "Kiosk", Money::new(500, "EUR"))
+            },
+        ];
+        let html = render(&expenses, &categories());
+        assert_eq!(parse(&html).expenses, expenses);
+        assert_eq!(render(&parse(&html).expenses, &categories()), html);
+    }
+
+    #[test]
+    fn test_round_trip_initial() {
+        let expenses = parse(INITIAL_HTML).expenses;
+        assert_eq!(parse(&render(&expenses, &categories())).expenses, expenses);
+    }
+
+    #[test]
+    fn test_merge_skips_known_ids() {
+        let existing = render(
+            &[expense(
+                "1",
+                Some("alltag"),
+                "REWE",
+                Money::new(1050, "EUR"),
+            )],
+            &categories(),
+        );
+        let new = [
+            expense("1", Some("alltag"), "REWE", Money::new(1050, "EUR")),
+            expense("2", None, "Kiosk", Money::new(100, "EUR")),
+            expense("2", None, "Kiosk", Money::new(100, "EUR")),
+        ];
+        let Merged {
+            html: merged,
+            added,
+            ..
+        } = merge(&existing, &new, &categories());
+        assert_eq!(added, 1);
+        let ids: Vec<String> = parse(&merged).expenses.into_iter().map(|x| x.id).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        assert!(merged.starts_with(&existing[..existing.len() - "</div>".len()]));
+    }
+
+    #[test]
+    fn test_merge_into_initial_content() {
+        let initial = "<div id=\"details\">\n  <!-- those will contain each entry created by js -->\n</div>\n";
+        let Merged {
+            html: merged,
+            added,
+            ..
+        } = merge(
+            initial,
+            &[expense("1", None, "Kiosk", Money::new(100, "EUR"))],
+            &categories(),
+        );
+        assert_eq!(added, 1);
+        assert_eq!(parse(&merged).expenses.len(), 1);
+        assert!(merged.ends_with("</details></div>\n"));
+    }
+
+    #[test]
+    fn test_merge_skips_known_fingerprints() {
+        let mut rewe = expense("100", Some("alltag"), "REWE", Money::new(1050, "EUR"));
+        rewe.fingerprint = Some("a".to_string());
+        let existing = render(std::slice::from_ref(&rewe), &categories());
+
+        // the same transaction of an overlapping export gets another id
+        let mut again = rewe.clone();
+        again.id = "101".to_string();
+        // another transaction of the same day may get the id of the existing one
+        let mut kiosk = expense("100", None, "Kiosk", Money::new(100, "EUR"));
+        kiosk.fingerprint = Some("b".to_string());
+
+        let Merged {
+            html: merged,
+            added,
+            ..
+        } = merge(&existing, &[again, kiosk], &categories());
+        assert_eq!(added, 1);
+        let parsed = parse(&merged).expenses;
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].title, "Kiosk");
+        assert_eq!(parsed[1].id, "101");
+        assert_eq!(parsed[1].fingerprint.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_merge_resolves_references_to_known_entries() {
+        let mut rewe = expense("100", Some("alltag"), "REWE", Money::new(3000, "EUR"));
+        rewe.fingerprint = Some("a".to_string());
+        let existing = render(std::slice::from_ref(&rewe), &categories());
+
+        // a later export gives the known expense another id
+        let mut again = rewe.clone();
+        again.id = "200".to_string();
+        let refund = Expense {
+            kind: Kind::Income,
+            fingerprint: Some("b".to_string()),
+            refund_of: Some("200".to_string()),
+            ..expense("201", None, "REWE", Money::new(500, "EUR"))
+        };
+
+        let merged = merge(&existing, &[again, refund], &categories());
+        assert_eq!(merged.added, 1);
+        let parsed = parse(&merged.html).expenses;
+        assert_eq!(parsed[1].id, "201");
+        assert_eq!(parsed[1].refund_of.as_deref(), Some("100"));
+    }
+
+    #[test]
+    fn test_merge_moves_refund_with_its_expense() {
+        let mut kiosk = expense("100", Some("alltag"), "Kiosk", Money::new(100, "EUR"));
+        kiosk.fingerprint = Some("k".to_string());
+        let existing = render(std::slice::from_ref(&kiosk), &categories());
+
+        let mut shop = expense("100", Some("drogerie"), "Shop", Money::new(3000, "EUR"));
+        shop.fingerprint = Some("a".to_string());
+        let refund = Expense {
+            kind: Kind::Income,
+            fingerprint: Some("b".to_string()),
+            refund_of: Some("100".to_string()),
+            ..expense("101", None, "Shop", Money::new(1000, "EUR"))
+        };
+
+        let merged = merge(&existing, &[shop, refund], &categories());
+        assert_eq!(merged.added, 2);
+        let parsed = parse(&merged.html).expenses;
+        let ids: Vec<&str> = parsed.iter().map(|x| &x.id as &str).collect();
+        assert_eq!(ids, vec!["100", "101", "102"]);
+        assert_eq!(parsed[2].refund_of.as_deref(), Some("101"));
+    }
+
+    #[test]
+    fn test_merge_keeps_transfer_pairs() {
+        let transfer = |id: &str, pair: &str, fingerprint: &str, amount: i64| Expense {
+            kind: Kind::Transfer,
+            fingerprint: Some(fingerprint.to_string()),
+            transfer_pair: Some(pair.to_string()),
+            ..expense(id, None, "Umbuchung", Money::new(amount, "EUR"))
+        };
+        let mut kiosk = expense("101", Some("alltag"), "Kiosk", Money::new(100, "EUR"));
+        kiosk.fingerprint = Some("k".to_string());
+        // the outgoing side was pushed with the export of the other account
+        let existing = render(
+            &[kiosk, transfer("300", "301", "out", -5000)],
+            &categories(),
+        );
+
+        let new = [
+            transfer("100", "101", "out", -5000),
+            transfer("101", "100", "in", 5000),
+        ];
+        let merged = merge(&existing, &new, &categories());
+        assert_eq!(merged.added, 1);
+        let parsed = parse(&merged.html).expenses;
+        assert_eq!(parsed[2].id, "102");
+        assert_eq!(parsed[2].transfer_pair.as_deref(), Some("300"));
+    }
+
+    #[test]
+    fn test_merge_keeps_split_parts_with_their_head() {
+        let entry = |id: &str, fingerprint: &str, split_of: Option<&str>| Expense {
+            fingerprint: Some(fingerprint.to_string()),
+            split_of: split_of.map(|x| x.to_string()),
+            ..expense(id, Some("alltag"), "REWE", Money::new(1000, "EUR"))
+        };
+        let new = [entry("100", "b", None), entry("101", "b-1", Some("100"))];
+
+        // another transaction has the id of the head
+        let existing = render(&[entry("100", "a", None)], &categories());
+        let merged = merge(&existing, &new, &categories());
+        let parsed = parse(&merged.html).expenses;
+        assert_eq!(parsed[1].id, "101");
+        assert_eq!(parsed[2].id, "102");
+        assert_eq!(parsed[2].split_of.as_deref(), Some("101"));
+
+        // only the head is already stored
+        let existing = render(&[entry("500", "b", None)], &categories());
+        let merged = merge(&existing, &new, &categories());
+        assert_eq!(merged.added, 1);
+        let parsed = parse(&merged.html).expenses;
+        assert_eq!(parsed[1].split_of.as_deref(), Some("500"));
+    }
+}
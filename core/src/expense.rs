@@ -0,0 +1,255 @@
+//! A single entry of a document.
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::Money;
+
+/// Time of day of ids derived from a date.
+///
+/// Noon keeps the day the same in all European time zones the frontend may
+/// display it in.
+const ID_TIME: NaiveTime = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+
+/// Whether money was spent or received, amounts of both kinds are positive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    Expense,
+    Income,
+    /// Money moved between own accounts, keeps the sign of the bank
+    /// transaction and is not part of any totals.
+    Transfer,
+}
+
+impl Kind {
+    /// The kind of a signed bank transaction amount.
+    pub fn of(amount: &Money) -> Self {
+        if amount.is_negative() {
+            Kind::Expense
+        } else {
+            Kind::Income
+        }
+    }
+
+    pub fn is_expense(&self) -> bool {
+        *self == Kind::Expense
+    }
+
+    /// Name used within documents and JSON.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Expense => "expense",
+            Kind::Income => "income",
+            Kind::Transfer => "transfer",
+        }
+    }
+}
+
+impl std::str::FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Kind::Expense, Kind::Income, Kind::Transfer]
+            .into_iter()
+            .find(|x| x.as_str() == s)
+            .ok_or_else(|| format!("unknown kind '{s}'"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Expense {
+    /// Timestamp in milliseconds, unique within a document.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Kind::is_expense")]
+    pub kind: Kind,
+    /// Day the bank booked the transaction, None for entries made in the frontend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub booking_date: Option<NaiveDate>,
+    /// Day the amount was credited or debited, if the bank provides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_date: Option<NaiveDate>,
+    /// Category id, None for entries without a known category.
+    pub category: Option<String>,
+    pub title: String,
+    pub amount: Money,
+    /// Identifies an imported bank transaction, see [`fingerprint`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Id of the expense this income refunds, e.g. a returned order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_of: Option<String>,
+    /// Id of the entry on the other own account of a transfer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_pair: Option<String>,
+    /// Id of the first entry of a bank transaction split across categories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_of: Option<String>,
+}
+
+impl Expense {
+    /// Returns an id on the date, `offset` milliseconds after [`ID_TIME`] keep
+    /// entries of the same day apart.
+    pub fn id_on(date: NaiveDate, offset: u32) -> String {
+        let millis = date.and_time(ID_TIME).and_utc().timestamp_millis() + i64::from(offset);
+        millis.to_string()
+    }
+
+    /// True for income refunding an expense, it reduces the expenses of its
+    /// category instead of counting as income.
+    pub fn is_refund(&self) -> bool {
+        self.kind == Kind::Income && self.refund_of.is_some()
+    }
+
+    /// The amount with the sign of a bank transaction, negative for expenses.
+    pub fn signed_amount(&self) -> Money {
+        match self.kind {
+            Kind::Expense => -self.amount.clone(),
+            Kind::Income | Kind::Transfer => self.amount.clone(),
+        }
+    }
+
+    /// The booking date or, for entries made in the frontend, the day of the id.
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.booking_date.or_else(|| {
+            let millis = self.id.parse::<i64>().ok()?;
+            DateTime::from_timestamp_millis(millis).map(|x| x.date_naive())
+        })
+    }
+}
+
+/// 64 bit FNV-1a, stable across platforms and Rust releases unlike `DefaultHasher`.
+fn fnv1a(fields: &[&str]) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET;
+    for field in fields {
+        // the separator keeps ("ab", "c") and ("a", "bc") apart
+        for byte in field.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Returns a fingerprint of a bank transaction that stays the same across
+/// overlapping exports.
+///
+/// `occurrence` counts preceding transactions of the export with the same
+/// date, amount, IBAN and reference, so that e.g. two equal coffees on the same
+/// day are kept apart.
+pub fn fingerprint(
+    date: NaiveDate,
+    amount: &Money,
+    iban: &str,
+    reference: &str,
+    occurrence: usize,
+) -> String {
+    // formatted like the bank exports to keep earlier fingerprints valid
+    let date = date.format("%d.%m.%Y").to_string();
+    let amount = amount.to_string();
+    let iban: String = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    let occurrence = occurrence.to_string();
+    format!(
+        "{:016x}",
+        fnv1a(&[&date, &amount, &iban, reference.trim(), &occurrence])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_id_on() {
+        assert_eq!(Expense::id_on(date("2026-01-02"), 0), "1767355200000");
+        assert_eq!(Expense::id_on(date("2026-01-02"), 7), "1767355200007");
+    }
+
+    #[test]
+    fn test_date() {
+        let mut expense = Expense {
+            id: Expense::id_on(date("2026-01-02"), 0),
+            kind: Kind::Expense,
+            booking_date: None,
+            value_date: None,
+            category: None,
+            title: String::new(),
+            amount: Money::zero("EUR"),
+            fingerprint: None,
+            refund_of: None,
+            transfer_pair: None,
+            split_of: None,
+        };
+        assert_eq!(expense.date(), Some(date("2026-01-02")));
+        expense.booking_date = Some(date("2025-12-31"));
+        assert_eq!(expense.date(), Some(date("2025-12-31")));
+    }
+
+    #[test]
+    fn test_kind() {
+        let expense = Money::new(-1250, "EUR");
+        assert_eq!(Kind::of(&expense), Kind::Expense);
+        assert_eq!(Kind::of(&expense.abs()), Kind::Income);
+
+        let mut entry = Expense {
+            id: "1".to_string(),
+            kind: Kind::Expense,
+            booking_date: None,
+            value_date: None,
+            category: None,
+            title: String::new(),
+            amount: expense.abs(),
+            fingerprint: None,
+            refund_of: None,
+            transfer_pair: None,
+            split_of: None,
+        };
+        assert_eq!(entry.signed_amount(), expense);
+        entry.kind = Kind::Income;
+        assert_eq!(entry.signed_amount(), expense.abs());
+        assert!(!entry.is_refund());
+        entry.refund_of = Some("0".to_string());
+        assert!(entry.is_refund());
+        assert_eq!("transfer".parse(), Ok(Kind::Transfer));
+        assert!("other".parse::<Kind>().is_err());
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let amount = Money::new(-1250, "EUR");
+        let fingerprint = fingerprint(date("2026-01-02"), &amount, "DE11 2233", "Einkauf", 0);
+        assert_eq!(fingerprint, "ece83ee4ca2cfd95");
+        assert_eq!(
+            super::fingerprint(date("2026-01-02"), &amount, "DE112233", " Einkauf", 0),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_differs() {
+        let amount = Money::new(-1250, "EUR");
+        let base = fingerprint(date("2026-01-02"), &amount, "DE11", "Einkauf", 0);
+        for other in [
+            fingerprint(date("2026-01-03"), &amount, "DE11", "Einkauf", 0),
+            fingerprint(
+                date("2026-01-02"),
+                &Money::new(-1251, "EUR"),
+                "DE11",
+                "Einkauf",
+                0,
+            ),
+            fingerprint(date("2026-01-02"), &amount, "DE12", "Einkauf", 0),
+            fingerprint(date("2026-01-02"), &amount, "DE11", "Einkauf 2", 0),
+            fingerprint(date("2026-01-02"), &amount, "DE11", "Einkauf", 1),
+        ] {
+            assert_ne!(base, other);
+        }
+    }
+}
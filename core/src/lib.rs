@@ -0,0 +1,10 @@
+//! Domain types shared between the `auseinnahmen` CLI and the `ausgabenzettel` backend.
+
+pub mod category;
+pub mod document;
+pub mod expense;
+pub mod money;
+
+pub use category::Category;
+pub use expense::{Expense, Kind, fingerprint};
+pub use money::Money;
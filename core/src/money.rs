@@ -0,0 +1,363 @@
+//! Exact money amounts stored in minor units.
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Minor units per major unit; all supported currencies use cents.
+const MINOR_PER_MAJOR: i64 = 100;
+/// Currency of amounts that do not name one, documents used to be in euro only.
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("empty amount")]
+    Empty,
+    #[error("invalid amount '{0}'")]
+    Invalid(String),
+    #[error("amount '{0}' has more than two decimal places")]
+    TooManyDecimals(String),
+    #[error("amount '{0}' is too large")]
+    Overflow(String),
+}
+
+/// Returns the ISO 4217 code for a currency as it is written in documents.
+pub fn currency_code(currency: &str) -> String {
+    match currency.trim() {
+        "" | "€" | "euro" | "Euro" => DEFAULT_CURRENCY.to_string(),
+        "$" => "USD".to_string(),
+        "£" => "GBP".to_string(),
+        x => x.to_uppercase(),
+    }
+}
+
+/// Returns how the currency is written in documents.
+///
+/// Euro keeps its symbol so that existing documents stay unchanged.
+pub fn currency_symbol(code: &str) -> &str {
+    match code {
+        "EUR" => "€",
+        x => x,
+    }
+}
+
+/// An amount in minor units (cents) of a currency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    pub minor: i64,
+    /// ISO 4217 code, e.g. `EUR`.
+    pub currency: String,
+}
+
+/// Parses unsigned `digits` with the given decimal and optional thousands separator.
+fn parse_unsigned(
+    original: &str,
+    digits: &str,
+    decimal: char,
+    thousands: Option<char>,
+) -> Result<i64, ParseError> {
+    let invalid = || ParseError::Invalid(original.to_string());
+    let overflow = || ParseError::Overflow(original.to_string());
+
+    let (integer, fraction) = match digits.split_once(decimal) {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (digits, ""),
+    };
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+
+    let integer = match thousands.filter(|x| integer.contains(*x)) {
+        Some(separator) => {
+            let groups: Vec<&str> = integer.split(separator).collect();
+            let valid =
+                (1..=3).contains(&groups[0].len()) && groups[1..].iter().all(|x| x.len() == 3);
+            if !valid {
+                return Err(invalid());
+            }
+            groups.concat()
+        }
+        None => integer.to_string(),
+    };
+    if !integer.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > 2 {
+        return Err(ParseError::TooManyDecimals(original.to_string()));
+    }
+
+    let major = if integer.is_empty() {
+        0
+    } else {
+        integer.parse::<i64>().map_err(|_| overflow())?
+    };
+    let minor = format!("{:0<2}", fraction).parse::<i64>().unwrap_or(0);
+    major
+        .checked_mul(MINOR_PER_MAJOR)
+        .and_then(|x| x.checked_add(minor))
+        .ok_or_else(overflow)
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: &str) -> Self {
+        Self {
+            minor,
+            currency: currency_code(currency),
+        }
+    }
+
+    pub fn zero(currency: &str) -> Self {
+        Self::new(0, currency)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            minor: self.minor.abs(),
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// Parses an amount as written in German bank exports.
+    ///
+    /// Uses `.` as thousands and `,` as decimal separator and accepts a leading
+    /// or trailing sign, e.g. `1.234,56`, `-12,3` or `12,34-`.
+    pub fn parse_german(s: &str, currency: &str) -> Result<Self, ParseError> {
+        Self::parse_with(s, currency, ',', Some('.'))
+    }
+
+    /// Parses an amount with the given separators like [`Money::parse_german`],
+    /// e.g. `-1,234.56` with `.` as decimal and `,` as thousands separator.
+    pub fn parse_with(
+        s: &str,
+        currency: &str,
+        decimal: char,
+        thousands: Option<char>,
+    ) -> Result<Self, ParseError> {
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let (negative, digits) = if let Some(x) = trimmed.strip_prefix('-') {
+            (true, x)
+        } else if let Some(x) = trimmed.strip_suffix('-') {
+            (true, x)
+        } else {
+            (false, trimmed.strip_prefix('+').unwrap_or(trimmed))
+        };
+        let minor = parse_unsigned(s, digits.trim(), decimal, thousands)?;
+        Ok(Self::new(if negative { -minor } else { minor }, currency))
+    }
+
+    /// The amount without currency using `.` as decimal separator, e.g. `-1234.50`.
+    pub fn amount(&self) -> String {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let minor = self.minor.unsigned_abs();
+        let per_major = MINOR_PER_MAJOR as u64;
+        format!("{sign}{}.{:02}", minor / per_major, minor % per_major)
+    }
+
+    /// The amount as floating point number, only meant for display and estimates.
+    pub fn as_f64(&self) -> f64 {
+        self.minor as f64 / MINOR_PER_MAJOR as f64
+    }
+
+    /// Converts into another currency by dividing through the rate, rounding to
+    /// the nearest minor unit.
+    pub fn convert(&self, rate: f64, currency: &str) -> Self {
+        Self::new((self.minor as f64 / rate).round() as i64, currency)
+    }
+
+    /// Adds both amounts when they share the currency.
+    pub fn checked_add(&self, other: &Money) -> Option<Money> {
+        if self.currency != other.currency {
+            return None;
+        }
+        Some(Self {
+            minor: self.minor.checked_add(other.minor)?,
+            currency: self.currency.clone(),
+        })
+    }
+}
+
+/// Formats like amounts are stored in documents, e.g. `12.00€` or `3.50USD`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.amount(), currency_symbol(&self.currency))
+    }
+}
+
+/// Parses amounts as stored in documents, e.g. `12.00€`, `12.00EUR` or `100`.
+///
+/// Amounts without currency are in [`DEFAULT_CURRENCY`]. A `,` is accepted as
+/// decimal separator when it is the last separator.
+impl FromStr for Money {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let split = trimmed
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ',' || c == '-'))
+            .unwrap_or(trimmed.len());
+        let (number, currency) = trimmed.split_at(split);
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(x) => (true, x),
+            None => (false, number),
+        };
+        let (decimal, thousands) = match (digits.rfind('.'), digits.rfind(',')) {
+            (Some(point), Some(comma)) if comma > point => (',', Some('.')),
+            (Some(_), Some(_)) => ('.', Some(',')),
+            (None, Some(_)) => (',', None),
+            _ => ('.', None),
+        };
+        let minor = parse_unsigned(s, digits, decimal, thousands)?;
+        Ok(Self::new(if negative { -minor } else { minor }, currency))
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            minor: -self.minor,
+            currency: self.currency,
+        }
+    }
+}
+
+/// Panics when the currencies differ, use [`Money::checked_add`] otherwise.
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(&rhs).unwrap_or_else(|| {
+            panic!(
+                "unable to add {} to {} without conversion",
+                rhs.currency, self.currency
+            )
+        })
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.clone() + rhs;
+    }
+}
+
+impl Sum for Money {
+    /// Sums amounts of the same currency, an empty iterator sums to zero euro.
+    fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
+        let first = iter.next().unwrap_or_else(|| Money::zero(DEFAULT_CURRENCY));
+        iter.fold(first, |a, b| a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur(minor: i64) -> Money {
+        Money::new(minor, "EUR")
+    }
+
+    #[test]
+    fn test_parse_german() {
+        assert_eq!(Money::parse_german("1.234,56", "EUR"), Ok(eur(123456)));
+        assert_eq!(Money::parse_german("-12,34", "EUR"), Ok(eur(-1234)));
+        assert_eq!(Money::parse_german("12,34-", "EUR"), Ok(eur(-1234)));
+        assert_eq!(Money::parse_german("+5", "EUR"), Ok(eur(500)));
+        assert_eq!(Money::parse_german("0,5", "EUR"), Ok(eur(50)));
+        assert_eq!(
+            Money::parse_german("1.000.000,00", "EUR"),
+            Ok(eur(100000000))
+        );
+        assert_eq!(
+            Money::parse_german("-43,91", "USD"),
+            Ok(Money::new(-4391, "USD"))
+        );
+    }
+
+    #[test]
+    fn test_parse_german_errors() {
+        assert_eq!(Money::parse_german("", "EUR"), Err(ParseError::Empty));
+        assert!(matches!(
+            Money::parse_german("1.23,45", "EUR"),
+            Err(ParseError::Invalid(_))
+        ));
+        assert!(matches!(
+            Money::parse_german("12,345", "EUR"),
+            Err(ParseError::TooManyDecimals(_))
+        ));
+        assert!(matches!(
+            Money::parse_german("12a", "EUR"),
+            Err(ParseError::Invalid(_))
+        ));
+        assert!(matches!(
+            Money::parse_german("99999999999999999999", "EUR"),
+            Err(ParseError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_with() {
+        assert_eq!(
+            Money::parse_with("-1,234.56", "EUR", '.', Some(',')),
+            Ok(eur(-123456))
+        );
+        assert_eq!(
+            Money::parse_with("1234.5", "EUR", '.', None),
+            Ok(eur(123450))
+        );
+        assert!(Money::parse_with("1.234,56", "EUR", '.', None).is_err());
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("12.00€".parse(), Ok(eur(1200)));
+        assert_eq!("12.00euro".parse(), Ok(eur(1200)));
+        assert_eq!("12.00EUR".parse(), Ok(eur(1200)));
+        assert_eq!("100".parse(), Ok(eur(10000)));
+        assert_eq!("12,5€".parse(), Ok(eur(1250)));
+        assert_eq!("1.234,56€".parse(), Ok(eur(123456)));
+        assert_eq!("-3.50USD".parse(), Ok(Money::new(-350, "USD")));
+        assert!("invalid".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for money in [eur(1200), eur(-5), Money::new(123456, "USD")] {
+            assert_eq!(money.to_string().parse(), Ok(money));
+        }
+        assert_eq!(eur(-5).to_string(), "-0.05€");
+    }
+
+    #[test]
+    fn test_sum_has_no_drift() {
+        let total: Money = std::iter::repeat_n(eur(10), 1000).sum();
+        assert_eq!(total, eur(10000));
+    }
+
+    #[test]
+    fn test_checked_add_requires_same_currency() {
+        assert_eq!(eur(1).checked_add(&Money::new(1, "USD")), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(Money::new(1100, "USD").convert(1.1, "EUR"), eur(1000));
+    }
+}
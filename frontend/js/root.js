@@ -1,48 +1,86 @@
 class Label {
-  constructor(title, description, index) {
-    this.index = Number(index);
+  constructor(title, description, id, parent, kind) {
+    this.id = id;
     this.title = title;
     this.description = description;
+    this.parent = parent || null;
+    // "income" or "expense"
+    this.kind = kind || "expense";
   }
   get toClass() {
-    return Label.toClass(this.index);
+    return Label.toClass(this.id);
 
   }
-  static toClass(index) {
-    return `cat${Number(index) + 1}`;
+  static toClass(id) {
+    return `cat-${id}`;
   }
 
   static fromClass(cl) {
-    if (!cl.startsWith("cat")) return null;
-    let cidx = cl.substring(3); // cat
-    return Number(cidx) - 1;
+    if (!cl.startsWith("cat-")) return null;
+    return cl.substring(4); // cat-
   }
 }
 
-const labels = [
-  new Label("Alltag", "Laufende Kosten für den täglichen Bedarf, wie Lebensmittel und Toilettenpapier", 0),
-  new Label("Ausgehen", "Ausgaben für Freizeitaktivitäten, wie Restaurantbesuche, Cafés, Bars, Takeout und Lieferdienste", 1),
-  new Label("Anschaffungen", "Investitionen in größere Käufe wie Elektronik, Möbel oder Haushaltsgeräte", 2),
-  new Label("Reisen", "Kosten für Reisen, einschließlich Tickets, Hotels und andere Reiseausgaben", 3),
-  new Label("Beauty & Wellness", "Ausgaben für Schönheitspflege und Entspannung, wie Massagen, Friseurbesuche und Wellnessangebote", 4),
-  new Label("Entertainment", "Ausgaben für Unterhaltung, wie Streaming-Abos, Kinotickets und andere Freizeitangebote", 5),
-  new Label("Keine", "Keine Kategorie.", 403),
-];
+// Filled on load from the categories the backend renders into
+// `#daily_label_select`.
+var labels = [];
 
 class Entry {
-  constructor(value, currency, label, timestamp, title) {
+  constructor(
+    value,
+    currency,
+    label,
+    timestamp,
+    title,
+    fingerprint,
+    bookingDate,
+    valueDate,
+    kind,
+    refundOf,
+    transferPair,
+    splitOf,
+  ) {
     this.value = Number(value).toFixed(2);
     this.currency = currency;
-    this.label = Number(label);
+    this.label = label;
     this.timestamp = Number(timestamp);
     this.title = title;
+    // set on imported bank transactions to detect them when importing again
+    this.fingerprint = fingerprint;
+    // YYYY-MM-DD as provided by the bank, the timestamp only identifies the entry
+    this.bookingDate = bookingDate;
+    this.valueDate = valueDate;
+    // "income", "expense" or "transfer" between own accounts, the value is
+    // positive except for outgoing transfers
+    this.kind = kind || "expense";
+    // id of the expense refunded by this income
+    this.refundOf = refundOf;
+    // id of the entry on the other own account of a transfer
+    this.transferPair = transferPair;
+    // id of the first entry of a bank transaction split across categories
+    this.splitOf = splitOf;
   }
 }
 
+// Refunds reduce the expenses of their category instead of counting as income.
+function isRefund(entry) {
+  return entry.kind == "income" && !!entry.refundOf;
+}
+
+// Day of the entry, the booking date when known.
+function entryDate(entry) {
+  if (entry.bookingDate) {
+    return new Date(`${entry.bookingDate}T12:00:00`);
+  }
+  return new Date(entry.timestamp);
+}
+
 class Filter {
   constructor() {
     // <select id="menu_overview_select">
     let mos = document.getElementById("menu_overview_select").value || "daily";
+    // "cashflow" or "expenses"
+    this.kind = document.getElementById("menu_kind_select").value || "cashflow";
     let selectedDate =
       document.getElementById("daily_date").valueAsDate || new Date();
     this.filter = mos;
@@ -50,10 +88,16 @@ class Filter {
   }
 
   show(entry) {
+    if (
+      this.kind == "expenses" &&
+      (entry.kind == "transfer" || (entry.kind == "income" && !isRefund(entry)))
+    ) {
+      return false;
+    }
     if (this.filter == "all") {
       return true;
     }
-    let ed = new Date(entry.timestamp);
+    let ed = entryDate(entry);
     if (this.filter == "daily") {
       return (
         this.selectedDate.getDate() == ed.getDate() &&
@@ -83,6 +127,7 @@ class Filter {
 window.onload = function() {
   addDocumentEventListener();
   storeCurrentEtag();
+  labels = readLabels();
   prepareDropDown(labels);
   updateEntries();
 };
@@ -110,14 +155,14 @@ function storeCurrentEtag() {
 }
 
 
-function createAgenda(total, labels) {
+function createAgenda(totals, labels, kind) {
   const container = document.getElementById("label_agenda");
   container.innerHTML = "";
   labels.sort((a, b) => b.value - a.value);
   let createDetail = (label) => {
     const details = document.createElement("details");
     const summary = document.createElement("summary");
-    const currency = "€";
+    const currency = baseCurrency();
 
     const titleSpan = document.createElement("span");
     titleSpan.textContent = label.title;
@@ -143,7 +188,7 @@ function createAgenda(total, labels) {
         document.querySelectorAll("#label_agenda details").forEach(d => {
           if (d !== detail) d.open = false;
         });
-        show_category = label.index;
+        show_category = label.id;
         redraw_entries();
       } else {
         const anyOtherOpen = document.querySelectorAll("#label_agenda details[open]").length > 0;
@@ -156,8 +201,17 @@ function createAgenda(total, labels) {
     container.appendChild(detail);
   });
 
-  const totalDetail = createDetail({ index: -1, title: "Total", value: total });
-  container.appendChild(totalDetail);
+  if (kind == "expenses") {
+    container.appendChild(
+      createDetail({ id: "total", title: "Total", value: totals.expenses }),
+    );
+    return;
+  }
+  [
+    { id: "income", title: "Income", value: totals.income },
+    { id: "expenses", title: "Expenses", value: totals.expenses },
+    { id: "net", title: "Net", value: totals.income - totals.expenses },
+  ].forEach((total) => container.appendChild(createDetail(total)));
 }
 
 function createEntryTemplate(show, label, entry) {
@@ -168,41 +222,53 @@ function createEntryTemplate(show, label, entry) {
         return `${num}`;
       }
     }
-    function human_readable_ts(stamp) {
-      const num = Number(stamp);
-      const ts = new Date(num);
-      if (isNaN(ts.getTime())) return "Invalid timestamp";
+    function date_display(ts) {
+      if (isNaN(ts.getTime())) return "Unknown";
       const year = ts.getFullYear();
       const month = two_digits(ts.getMonth() + 1);
       const day = two_digits(ts.getDate());
-      const hour = two_digits(ts.getHours());
-      const minute = two_digits(ts.getMinutes());
-      const seconds = two_digits(ts.getSeconds());
-      return `${year}-${month}-${day}T${hour}:${minute}:${seconds}`;
+      return `${year}-${month}-${day}`;
     }
-function date_display(stamp) {
-      const num = Number(stamp);
-      if (isNaN(num)) return "Unknown";
-      const ts = new Date(num);
-      if (isNaN(ts.getTime())) return "Unknown";
-      const year = ts.getFullYear();
-      const monthVal = ts.getMonth() + 1;
-      const dayVal = ts.getDate();
-      const hourVal = ts.getHours();
-      const minuteVal = ts.getMinutes();
-      const month = two_digits(monthVal);
-      const day = two_digits(dayVal);
-      const hour = two_digits(hourVal);
-      const minute = two_digits(minuteVal);
-      return `${year}-${month}-${day} ${hour}:${minute}`;
+    function date_row(title, value) {
+      const row = document.createElement("div");
+      row.classList.add("row");
+      const row_label = document.createElement("span");
+      row_label.textContent = title;
+      const row_value = document.createElement("span");
+      row_value.classList.add("right-align");
+      row_value.textContent = value;
+      row.appendChild(row_label);
+      row.appendChild(row_value);
+      return row;
     }
   const details = document.createElement("details");
   details.classList.add(Label.toClass(entry.label));
   details.id = entry.timestamp;
+  if (entry.fingerprint) {
+    details.dataset.fingerprint = entry.fingerprint;
+  }
+  if (entry.bookingDate) {
+    details.dataset.bookingDate = entry.bookingDate;
+  }
+  if (entry.valueDate) {
+    details.dataset.valueDate = entry.valueDate;
+  }
+  if (entry.kind != "expense") {
+    details.dataset.kind = entry.kind;
+  }
+  if (entry.refundOf) {
+    details.dataset.refundOf = entry.refundOf;
+  }
+  if (entry.transferPair) {
+    details.dataset.transferPair = entry.transferPair;
+  }
+  if (entry.splitOf) {
+    details.dataset.splitOf = entry.splitOf;
+  }
 
-  const hrts = human_readable_ts(entry.timestamp);
+  const date = date_display(entryDate(entry));
   const titleSpan = document.createElement("span");
-  titleSpan.textContent = entry.title || hrts;
+  titleSpan.textContent = entry.title || date;
   const currencySpan = document.createElement("span");
   currencySpan.textContent = `${entry.value}${entry.currency}`;
 
@@ -222,16 +288,10 @@ function date_display(stamp) {
   category_desc.appendChild(category_title);
   detail_container.appendChild(category_desc);
 
-  const date_desc = document.createElement("div");
-  date_desc.classList.add("row");
-  const date_label = document.createElement("span");
-  date_label.textContent = "Date";
-  const date_value = document.createElement("span");
-  date_value.classList.add("right-align");
-  date_value.textContent = date_display(entry.timestamp);
-  date_desc.appendChild(date_label);
-  date_desc.appendChild(date_value);
-  detail_container.appendChild(date_desc);
+  detail_container.appendChild(date_row("Date", date));
+  if (entry.valueDate && entry.valueDate != entry.bookingDate) {
+    detail_container.appendChild(date_row("Value date", entry.valueDate));
+  }
 
   const removeLink = document.createElement("a");
   removeLink.textContent = "remove";
@@ -281,13 +341,50 @@ function convertEmToPx(emValue, context) {
   return emValue * fontSize;
 }
 
+function readLabels() {
+  const options = document.getElementById("daily_label_select").options;
+  const result = [...options].map(
+    (option) =>
+      new Label(
+        option.dataset.title || option.textContent,
+        option.title,
+        option.value,
+        option.dataset.parent,
+        option.dataset.kind,
+      ),
+  );
+  if (!result.some((label) => label.id === "unknown")) {
+    result.push(new Label("Keine", "Keine Kategorie.", "unknown"));
+  }
+  if (!result.some((label) => label.id === "unknown-income")) {
+    result.push(
+      new Label(
+        "Sonstige Einnahmen",
+        "Einnahmen ohne Kategorie.",
+        "unknown-income",
+        null,
+        "income",
+      ),
+    );
+  }
+  return result;
+}
+
 function prepareDropDown(labels) {
   const details = document.getElementById("daily_label_select");
   details.innerHTML = "";
   labels.forEach((label) => {
     const option = document.createElement("option");
-    option.textContent = label.title;
-    option.value = label.index;
+    option.textContent = label.parent ? `↳ ${label.title}` : label.title;
+    option.title = label.description;
+    option.value = label.id;
+    option.dataset.title = label.title;
+    if (label.parent) {
+      option.dataset.parent = label.parent;
+    }
+    if (label.kind == "income") {
+      option.dataset.kind = label.kind;
+    }
     details.appendChild(option);
   });
 }
@@ -311,7 +408,20 @@ function getHTMLEntries(cached) {
       .find((x) => x != null);
     let title = details[i].children[0].children[0].textContent;
     let vc = splitCurrencyLabel(details[i].children[0].children[1].textContent);
-    const entry = new Entry(vc.value, vc.currency, label, timestamp, title);
+    const entry = new Entry(
+      vc.value,
+      vc.currency,
+      label,
+      timestamp,
+      title,
+      details[i].dataset.fingerprint,
+      details[i].dataset.bookingDate,
+      details[i].dataset.valueDate,
+      details[i].dataset.kind,
+      details[i].dataset.refundOf,
+      details[i].dataset.transferPair,
+      details[i].dataset.splitOf,
+    );
 
     if (cached.findIndex((e) => e.timestamp == entry.timestamp) == -1) {
       results.push(entry);
@@ -321,6 +431,42 @@ function getHTMLEntries(cached) {
   return results;
 }
 
+// Returns the top level label of the given (sub)category label.
+function rootLabel(label) {
+  let current = label;
+  while (current && current.parent) {
+    const parent = labels.find((item) => item.id === current.parent);
+    if (!parent || parent === label) break;
+    current = parent;
+  }
+  return current;
+}
+
+// Returns the top level label the entry is summed up in, entries whose
+// category does not match their kind count as unknown of their kind.
+function agendaLabel(label, entry) {
+  const kind = isRefund(entry) ? "expense" : entry.kind;
+  const root = rootLabel(label);
+  if (root && root.kind == kind) return root;
+  const unknown = kind == "income" ? "unknown-income" : "unknown";
+  return labels.find((item) => item.id === unknown);
+}
+
+// The first currency is the base currency all totals are converted into.
+function baseCurrency() {
+  const options = document.getElementById("daily_currency").options;
+  return options.length > 0 ? options[0].value : "€";
+}
+
+// Converts the value into the base currency using the latest known rate.
+function toBaseCurrency(value, currency) {
+  const option = [...document.getElementById("daily_currency").options].find(
+    (option) => option.value === currency,
+  );
+  const rate = option ? Number(option.dataset.rate) : 1;
+  return Number(value) / (rate || 1);
+}
+
 function getRootColor(color) {
   return getComputedStyle(document.documentElement)
     .getPropertyValue(color)
@@ -340,9 +486,8 @@ function redraw_entries() {
   const drawData = storedData.filter((e) => e.event !== "remove");
   details.innerHTML = "";
   drawData.forEach((element) => {
-    let show = filtered.show(element) && (show_category == null || show_category == element.label );
-
-      let idx = labels.findIndex((item) => item.index === element.label);
+      let idx = labels.findIndex((item) => item.id === element.label);
+      let show = filtered.show(element) && (show_category == null || show_category == agendaLabel(labels[idx], element).id);
 
       const entry = createEntryTemplate(show, labels[idx], element);
       details.appendChild(entry);
@@ -362,6 +507,7 @@ function updateEntries() {
   details.innerHTML = "";
   const prepared_labels = labels.map((label) => {
     label.value = 0;
+    label.color = getRootColor(`--${label.toClass}`);
     return label;
   });
 
@@ -371,34 +517,34 @@ function updateEntries() {
   const drawData = storedData.filter((e) => e.event !== "remove");
   
   const filtered = new Filter();
-  const colors = [
-    getRootColor("--cat1"),
-    getRootColor("--cat2"),
-    getRootColor("--cat3"),
-    getRootColor("--cat4"),
-    getRootColor("--cat5"),
-    getRootColor("--cat6"),
-    getRootColor("--cat404"),
-  ];
-  var total = 0;
+  const totals = { income: 0, expenses: 0 };
   const summaries = drawData.reverse().reduce((p, c) => {
     if (!dont_draw.includes(c.timestamp)) {
-      let idx = p.findIndex((item) => item.index === Number(c.label));
+      let idx = p.findIndex((item) => item.id === c.label);
       let show = filtered.show(c);
       let e = p[idx];
-      if (show) {
-        e.value += Number(c.value);
-        total += Number(c.value);
-        e.color = colors[idx];
-        p[idx] = e;
+      // transfers only move money between own accounts
+      if (show && c.kind != "transfer") {
+        // subcategories are rolled up into their top level category
+        let value = toBaseCurrency(c.value, c.currency);
+        if (isRefund(c)) value = -value;
+        agendaLabel(e, c).value += value;
+        if (c.kind == "income" && !isRefund(c)) {
+          totals.income += value;
+        } else {
+          totals.expenses += value;
+        }
       }
       const entry = createEntryTemplate(show, e, c);
       details.appendChild(entry);
     }
     return p;
-  }, prepared_labels);
+  }, prepared_labels).filter(
+    (label) =>
+      !label.parent && (filtered.kind != "expenses" || label.kind != "income"),
+  );
 
-  createAgenda(total, summaries);
+  createAgenda(totals, summaries, filtered.kind);
   if (!agenda_size) {
     let padding = summaries.length * 0.25 * 2;
     let margin = summaries.length * 0.5;
@@ -450,6 +596,11 @@ function addDocumentEventListener() {
     .addEventListener("change", function() {
       updateEntries();
     });
+  document
+    .getElementById("menu_kind_select")
+    .addEventListener("change", function() {
+      updateEntries();
+    });
 
   document
     .getElementById("daily_label_select")
@@ -460,8 +611,9 @@ function addDocumentEventListener() {
   document.getElementById("daily_form").addEventListener("submit", function(event) {
     event.preventDefault();
     const value = document.getElementById("daily_input").value;
-    const currency = "€";
+    const currency = document.getElementById("daily_currency").value || "€";
     const label = document.getElementById("daily_label_select").value;
+    const kind = labels.find((item) => item.id === label)?.kind;
 
     if (value && label) {
       let selectedDate =
@@ -479,6 +631,10 @@ function addDocumentEventListener() {
         label,
         selectedDate.getTime(),
         title.value,
+        undefined,
+        undefined,
+        undefined,
+        kind,
       );
       dailyEntries.push(newEntry);
       title.value = null;